@@ -0,0 +1,90 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+// Times one collision pass over a bullet hell sized scene: thousands of shots against hundreds of enemies.
+// Run with `cargo bench`, prints the average time per pass.
+
+use std::time::Instant;
+
+use sky_combat::collider::Collider;
+use sky_combat::collision::{Collisions, Handle, Layer, Mask};
+use sky_combat::vec2::Vec2;
+
+const ENEMIES: usize = 300;
+const PLAYER_BULLETS: usize = 3000;
+const ENEMY_BULLETS: usize = 2000;
+const PASSES: u32 = 200;
+
+// Small LCG, so every run lays the scene out the same way
+struct Scatter(u64);
+
+impl Scatter {
+    fn next(&mut self, min: f64, max: f64) -> f64 {
+        self.0 = self.0.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        min + (self.0 >> 11) as f64 / (1u64 << 53) as f64 * (max - min)
+    }
+
+    fn point(&mut self) -> Vec2 {
+        Vec2::new(self.next(-128.0, 128.0), self.next(0.0, 256.0))
+    }
+}
+
+fn fill(collisions: &mut Collisions, scatter: &mut Scatter) {
+    collisions.clear();
+    collisions.add_collider(Handle::Player, Layer::Player, Mask::NONE, Vec2::new(0.0, 25.0), Collider::Circle { radius: 3.0 });
+
+    // Shots are swept along the last move of a fast frame, like Projectile::collider
+    for i in 0..PLAYER_BULLETS {
+        let swept = Collider::Capsule { start: Vec2::ZERO, end: Vec2::new(0.0, -6.0), radius: 2.0 };
+        collisions.add_collider(Handle::PlayerBullet(i), Layer::PlayerBullet, Layer::PlayerBullet.default_mask(), scatter.point(), swept);
+    }
+    for i in 0..ENEMIES {
+        let body = Collider::Circle { radius: 5.0 };
+        collisions.add_collider(Handle::Enemy(i), Layer::Enemy, Layer::Enemy.default_mask(), scatter.point(), body);
+    }
+    for i in 0..ENEMY_BULLETS {
+        let swept = Collider::Capsule { start: Vec2::ZERO, end: Vec2::new(0.0, 3.0), radius: 2.0 };
+        collisions.add_collider(Handle::EnemyBullet(i), Layer::EnemyBullet, Layer::EnemyBullet.default_mask(), scatter.point(), swept);
+    }
+}
+
+fn main() {
+    let mut collisions = Collisions::new();
+    let mut scatter = Scatter(1);
+
+    // Warm up allocations before timing
+    fill(&mut collisions, &mut scatter);
+    let mut hits = collisions.hits().len();
+
+    let start = Instant::now();
+    for _ in 0..PASSES {
+        fill(&mut collisions, &mut scatter);
+        hits += collisions.hits().len();
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{} player bullets, {} enemy bullets, {} enemies: {:.3} ms per pass ({} hits in total)",
+        PLAYER_BULLETS,
+        ENEMY_BULLETS,
+        ENEMIES,
+        elapsed.as_secs_f64() * 1000.0 / PASSES as f64,
+        hits
+    );
+}
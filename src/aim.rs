@@ -0,0 +1,157 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use crate::rng::Rng;
+use crate::vec2::Vec2;
+
+// Someone being shot at. Velocity is in pixels per game time unit, same as projectile speeds.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Target {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+// How a gunner works out where to shoot
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aim {
+    // How much of the player's movement to lead, 0 shoots where they are now and 1 where the shot would meet them
+    pub lead: f64,
+    // Shots miss by up to this many degrees either side
+    pub error: f64,
+}
+
+impl Aim {
+    // Straight at where the player is now, never missing on purpose
+    pub const PLAIN: Aim = Aim { lead: 0.0, error: 0.0 };
+
+    // Better gunners lead more and miss by less
+    pub fn with_skill(&self, skill: f64) -> Aim {
+        Aim {
+            lead: (self.lead * skill).min(1.0),
+            error: self.error / skill,
+        }
+    }
+
+    // Unit direction, without error, to hit `target` with a shot flying at `speed`
+    pub fn direction(&self, origin: Vec2, target: Target, speed: f64) -> Vec2 {
+        let aim_point = match intercept(origin, target, speed) {
+            Some(meeting) => target.position.lerp(meeting, self.lead),
+            None => target.position,
+        };
+        (aim_point - origin).normalised()
+    }
+
+    // Degrees to turn a shot off the aimed direction
+    pub fn random_error(&self, rng: &mut Rng) -> f64 {
+        (rng.next_f64() * 2.0 - 1.0) * self.error
+    }
+}
+
+// Where a shot leaving `origin` at `speed` meets the target if it keeps flying straight.
+// None when the target is too fast to catch.
+pub fn intercept(origin: Vec2, target: Target, speed: f64) -> Option<Vec2> {
+    let delta = target.position - origin;
+    let velocity = target.velocity;
+
+    // Solve |delta + velocity * time| = speed * time for the earliest time ahead
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2.0 * delta.dot(velocity);
+    let c = delta.length_squared();
+
+    let time = if a.abs() < 1e-9 {
+        // Same speed as the shot, only catches it coming closer
+        if b >= 0.0 {
+            return None;
+        }
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        let (first, second) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+        match (first.min(second), first.max(second)) {
+            (earliest, _) if earliest >= 0.0 => earliest,
+            (_, latest) if latest >= 0.0 => latest,
+            _ => return None,
+        }
+    };
+
+    Some(target.position + velocity * time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHOT_SPEED: f64 = 10.0;
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(actual.distance(expected) < 1e-9, "{:?} is not {:?}", actual, expected);
+    }
+
+    fn full_lead() -> Aim {
+        Aim { lead: 1.0, error: 0.0 }
+    }
+
+    #[test]
+    fn stationary_target_is_aimed_at_directly() {
+        let target = Target { position: Vec2::new(30.0, 40.0), velocity: Vec2::ZERO };
+        assert_near(intercept(Vec2::ZERO, target, SHOT_SPEED).unwrap(), target.position);
+        assert_near(full_lead().direction(Vec2::ZERO, target, SHOT_SPEED), Vec2::new(0.6, 0.8));
+        assert_near(Aim::PLAIN.direction(Vec2::ZERO, target, SHOT_SPEED), Vec2::new(0.6, 0.8));
+    }
+
+    // 100 pixels up, crossing at 6 a time unit: |(6t, 100)| = 10t meets at t = 12.5, so at (75, 100)
+    #[test]
+    fn crossing_target_is_met_where_the_shot_gets_there() {
+        let target = Target { position: Vec2::new(0.0, 100.0), velocity: Vec2::new(6.0, 0.0) };
+        assert_near(intercept(Vec2::ZERO, target, SHOT_SPEED).unwrap(), Vec2::new(75.0, 100.0));
+        assert_near(full_lead().direction(Vec2::ZERO, target, SHOT_SPEED), Vec2::new(0.6, 0.8));
+
+        // Half a lead aims halfway between where the target is and where it will be
+        let half = Aim { lead: 0.5, error: 0.0 };
+        assert_near(half.direction(Vec2::ZERO, target, SHOT_SPEED), Vec2::new(37.5, 100.0).normalised());
+        assert_near(Aim::PLAIN.direction(Vec2::ZERO, target, SHOT_SPEED), Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn uncatchable_target_falls_back_to_plain_aim() {
+        let origin = Vec2::ZERO;
+        let position = Vec2::new(0.0, 100.0);
+        let uncatchable = [
+            // Faster than the shot, going across
+            Vec2::new(20.0, 0.0),
+            // Faster than the shot, going away
+            Vec2::new(0.0, 20.0),
+            // As fast as the shot, going away
+            Vec2::new(0.0, SHOT_SPEED),
+        ];
+        for velocity in uncatchable {
+            let target = Target { position, velocity };
+            assert_eq!(intercept(origin, target, SHOT_SPEED), None, "{:?}", velocity);
+            assert_near(full_lead().direction(origin, target, SHOT_SPEED), Vec2::new(0.0, 1.0));
+        }
+
+        // As fast as the shot but coming closer is still caught
+        let closing = Target { position, velocity: Vec2::new(0.0, -SHOT_SPEED) };
+        assert_near(intercept(origin, closing, SHOT_SPEED).unwrap(), Vec2::new(0.0, 50.0));
+    }
+}
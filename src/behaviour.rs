@@ -0,0 +1,382 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use std::f64::consts::TAU;
+use std::rc::Rc;
+
+use crate::path::Spline;
+use crate::vec2::Vec2;
+use crate::world::WALK_AREA_MAX_X;
+use crate::behaviour::Behaviour::{Dive, FlyL2R, FlyR2L, FollowPath, HoverStrafe, Kamikaze, Orbit, SineWeave, ZigZag};
+
+// How much faster than cruising speed diving and ramming enemies fly
+const DIVE_SPEEDUP: f64 = 2.0;
+const RAM_SPEEDUP: f64 = 1.5;
+// Keep strafing enemies this far from the screen edge
+const STRAFE_MARGIN: f64 = 10.0;
+
+// How an enemy moves. Distances are in pixels, times in game time units (delta time scaled by game speed)
+// and turn rates in degrees per time unit.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Behaviour {
+    FlyL2R,
+    FlyR2L,
+    // Weaves side to side around the entry x while descending
+    SineWeave { amplitude: f64, period: f64 },
+    // Like SineWeave but with sharp turns
+    ZigZag { width: f64, period: f64 },
+    // Dives at the player and climbs away once within pull_up_distance above them
+    Dive { pull_up_distance: f64 },
+    // Descends to hover_y, strafes wall to wall for a while, then carries on down
+    HoverStrafe { hover_y: f64, strafe_speed: f64, duration: f64 },
+    // Circles around a slowly descending point
+    Orbit { radius: f64, angular_speed: f64 },
+    // Steers into the player, turning at most turn_rate degrees per time unit, until it passes them
+    Kamikaze { turn_rate: f64 },
+    // Flies along an authored path at constant speed, shifted by the entry x.
+    // Mirrored flips it left to right, so one path serves both sides of the screen.
+    FollowPath { path: Rc<Spline>, mirrored: bool },
+}
+
+impl Behaviour {
+    pub const NAMES: [&'static str; 8] = ["fly_l2r", "fly_r2l", "sine", "zigzag", "dive", "hover_strafe", "orbit", "kamikaze"];
+
+    // Behaviour with its default parameters
+    pub fn from_name(name: &str) -> Option<Behaviour> {
+        match name {
+            "fly_l2r" => Some(FlyL2R),
+            "fly_r2l" => Some(FlyR2L),
+            "sine" => Some(SineWeave { amplitude: 30.0, period: 20.0 }),
+            "zigzag" => Some(ZigZag { width: 30.0, period: 20.0 }),
+            "dive" => Some(Dive { pull_up_distance: 40.0 }),
+            "hover_strafe" => Some(HoverStrafe { hover_y: 180.0, strafe_speed: 5.0, duration: 50.0 }),
+            "orbit" => Some(Orbit { radius: 30.0, angular_speed: 0.3 }),
+            "kamikaze" => Some(Kamikaze { turn_rate: 6.0 }),
+            _ => None,
+        }
+    }
+
+    pub fn parameter_names(&self) -> &'static [&'static str] {
+        match self {
+            FlyL2R | FlyR2L => &[],
+            SineWeave { .. } => &["amplitude", "period"],
+            ZigZag { .. } => &["width", "period"],
+            Dive { .. } => &["pull_up_distance"],
+            HoverStrafe { .. } => &["hover_y", "strafe_speed", "duration"],
+            Orbit { .. } => &["radius", "angular_speed"],
+            Kamikaze { .. } => &["turn_rate"],
+            FollowPath { .. } => &[],
+        }
+    }
+
+    // Returns false if this behaviour has no such parameter
+    pub fn set_parameter(&mut self, name: &str, value: f64) -> bool {
+        let parameter = match (self, name) {
+            (SineWeave { amplitude, .. }, "amplitude") => amplitude,
+            (SineWeave { period, .. }, "period") => period,
+            (ZigZag { width, .. }, "width") => width,
+            (ZigZag { period, .. }, "period") => period,
+            (Dive { pull_up_distance }, "pull_up_distance") => pull_up_distance,
+            (HoverStrafe { hover_y, .. }, "hover_y") => hover_y,
+            (HoverStrafe { strafe_speed, .. }, "strafe_speed") => strafe_speed,
+            (HoverStrafe { duration, .. }, "duration") => duration,
+            (Orbit { radius, .. }, "radius") => radius,
+            (Orbit { angular_speed, .. }, "angular_speed") => angular_speed,
+            (Kamikaze { turn_rate }, "turn_rate") => turn_rate,
+            _ => return false,
+        };
+        *parameter = value;
+        true
+    }
+
+    // Drift towards the middle of the screen from wherever the enemy enters
+    pub fn toward_center(position_x: f64) -> Behaviour {
+        match position_x < 0.0 {
+            true => FlyL2R,
+            false => FlyR2L
+        }
+    }
+}
+
+// Per enemy state the behaviours need on top of position
+#[derive(Clone, Debug)]
+pub struct Motion {
+    age: f64,
+    origin_x: f64,
+    anchor_y: f64,
+    heading: Vec2,
+    strafe_direction: f64,
+    phase_time: f64,
+    // Distance flown along a path
+    travelled: f64,
+    // Dive has pulled up, HoverStrafe is done strafing, Kamikaze has passed the player
+    leaving: bool,
+}
+
+impl Motion {
+    pub fn new(position: Vec2) -> Motion {
+        Motion {
+            age: 0.0,
+            origin_x: position.x,
+            anchor_y: position.y,
+            // Straight down
+            heading: Vec2::new(0.0, -1.0),
+            strafe_direction: if position.x < 0.0 { 1.0 } else { -1.0 },
+            phase_time: 0.0,
+            travelled: 0.0,
+            leaving: false,
+        }
+    }
+
+    // Moves position by one update of `step` time units. Target is where the player currently is.
+    pub fn step(&mut self, behaviour: &Behaviour, position: Vec2, target: Vec2, speed: f64, step: f64) -> Vec2 {
+        let Vec2 { mut x, mut y } = position;
+        self.age += step;
+
+        match *behaviour {
+            FlyL2R => {
+                y -= speed * step;
+                x += speed / 2.0 * step;
+            }
+            FlyR2L => {
+                y -= speed * step;
+                x -= speed / 2.0 * step;
+            }
+            SineWeave { amplitude, period } => {
+                y -= speed * step;
+                x = self.origin_x + amplitude * (TAU * self.age / period).sin();
+            }
+            ZigZag { width, period } => {
+                y -= speed * step;
+                x = self.origin_x + width * triangle_wave(self.age / period + 0.25);
+            }
+            Dive { pull_up_distance } => {
+                if !self.leaving {
+                    Vec2 { x, y } = move_toward(Vec2::new(x, y), target, speed * DIVE_SPEEDUP * step);
+                    if y - target.y <= pull_up_distance {
+                        self.leaving = true;
+                    }
+                } else {
+                    y += speed * DIVE_SPEEDUP * step;
+                }
+            }
+            HoverStrafe { hover_y, strafe_speed, duration } => {
+                if self.leaving || y > hover_y {
+                    y = match self.leaving {
+                        true => y - speed * step,
+                        false => (y - speed * step).max(hover_y),
+                    };
+                } else {
+                    self.phase_time += step;
+                    x += self.strafe_direction * strafe_speed * step;
+
+                    let edge = WALK_AREA_MAX_X - STRAFE_MARGIN;
+                    if x.abs() > edge {
+                        x = x.clamp(-edge, edge);
+                        self.strafe_direction = -self.strafe_direction;
+                    }
+                    if self.phase_time >= duration {
+                        self.leaving = true;
+                    }
+                }
+            }
+            Orbit { radius, angular_speed } => {
+                self.anchor_y -= speed / 2.0 * step;
+                let angle = self.age * angular_speed;
+                // Offset so the orbit starts where the enemy entered
+                x = self.origin_x + radius * (angle.cos() - 1.0);
+                y = self.anchor_y + radius * angle.sin();
+            }
+            Kamikaze { turn_rate } => {
+                if !self.leaving {
+                    let difference = self.heading.angle_to(target - Vec2::new(x, y));
+                    let max_turn = turn_rate * step;
+                    self.heading = self.heading.rotate(difference.clamp(-max_turn, max_turn));
+                    if y < target.y {
+                        self.leaving = true;
+                    }
+                }
+
+                let velocity = self.heading * (speed * RAM_SPEEDUP);
+                x += velocity.x * step;
+                y += velocity.y * step;
+            }
+            FollowPath { ref path, mirrored } => {
+                self.travelled += speed * step;
+                let point = path.point_at(self.travelled);
+                x = self.origin_x + if mirrored { -point.x } else { point.x };
+                y = point.y;
+            }
+        }
+
+        Vec2::new(x, y)
+    }
+}
+
+// Goes -1 to 1 and back once per unit of phase, crossing 0 going up at 0.25
+fn triangle_wave(phase: f64) -> f64 {
+    let t = phase.rem_euclid(1.0);
+    match t < 0.5 {
+        true => 4.0 * t - 1.0,
+        false => 3.0 - 4.0 * t,
+    }
+}
+
+fn move_toward(position: Vec2, target: Vec2, distance: f64) -> Vec2 {
+    let delta = target - position;
+    if delta.length() <= distance {
+        return target;
+    }
+    position + delta.normalised() * distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path;
+
+    const SPEED: f64 = 2.0;
+    const STEP: f64 = 0.5;
+
+    // Runs a behaviour for a number of updates from start, with the player parked at target
+    fn fly(behaviour: &Behaviour, start: Vec2, target: Vec2, updates: usize) -> (Motion, Vec<Vec2>) {
+        let mut motion = Motion::new(start);
+        let mut position = start;
+        let mut positions = vec![];
+        for _ in 0..updates {
+            position = motion.step(behaviour, position, target, SPEED, STEP);
+            positions.push(position);
+        }
+        (motion, positions)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} is not {}", actual, expected);
+    }
+
+    #[test]
+    fn fly_across_descends_at_a_slant() {
+        let (_, positions) = fly(&FlyL2R, Vec2::new(-50.0, 250.0), Vec2::default(), 10);
+        assert_close(positions[9].x, -50.0 + SPEED / 2.0 * STEP * 10.0);
+        assert_close(positions[9].y, 250.0 - SPEED * STEP * 10.0);
+
+        let (_, positions) = fly(&FlyR2L, Vec2::new(50.0, 250.0), Vec2::default(), 10);
+        assert_close(positions[9].x, 50.0 - SPEED / 2.0 * STEP * 10.0);
+    }
+
+    #[test]
+    fn sine_weave_peaks_a_quarter_period_in() {
+        let sine = SineWeave { amplitude: 30.0, period: 20.0 };
+        // 10 updates of 0.5 is a quarter of the period, 40 is all of it
+        let (_, positions) = fly(&sine, Vec2::new(10.0, 250.0), Vec2::default(), 40);
+        assert_close(positions[9].x, 40.0);
+        assert_close(positions[29].x, -20.0);
+        assert!((positions[39].x - 10.0).abs() < 1e-6);
+        assert_close(positions[39].y, 250.0 - SPEED * STEP * 40.0);
+    }
+
+    #[test]
+    fn zigzag_turns_at_its_width() {
+        let zigzag = ZigZag { width: 30.0, period: 20.0 };
+        let (_, positions) = fly(&zigzag, Vec2::new(0.0, 250.0), Vec2::default(), 40);
+        assert_close(positions[9].x, 30.0);
+        assert_close(positions[19].x, 0.0);
+        assert_close(positions[29].x, -30.0);
+        assert!(positions.iter().all(|position| position.x.abs() <= 30.0 + 1e-9));
+    }
+
+    #[test]
+    fn dive_pulls_up_above_the_player() {
+        let dive = Dive { pull_up_distance: 40.0 };
+        let target = Vec2::new(0.0, 20.0);
+        let (motion, positions) = fly(&dive, Vec2::new(0.0, 250.0), target, 200);
+        assert!(motion.leaving);
+
+        let lowest = positions.iter().map(|position| position.y).fold(f64::MAX, f64::min);
+        assert!(lowest - target.y <= 40.0);
+        assert!(lowest > target.y);
+        assert!(positions[199].y > lowest);
+    }
+
+    #[test]
+    fn hover_strafe_holds_height_then_leaves() {
+        let hover = HoverStrafe { hover_y: 180.0, strafe_speed: 5.0, duration: 10.0 };
+        let (motion, positions) = fly(&hover, Vec2::new(0.0, 200.0), Vec2::default(), 30);
+        // 20 units down at 1 per update, then strafing at hover height
+        assert_close(positions[19].y, 180.0);
+        assert_close(positions[19].x, 0.0);
+        assert_close(positions[29].y, 180.0);
+        assert!(positions[29].x < 0.0);
+        assert!(!motion.leaving);
+
+        let (motion, positions) = fly(&hover, Vec2::new(0.0, 200.0), Vec2::default(), 60);
+        assert!(motion.leaving);
+        assert!(positions[59].y < 180.0);
+        let edge = WALK_AREA_MAX_X - STRAFE_MARGIN;
+        assert!(positions.iter().all(|position| position.x.abs() <= edge));
+    }
+
+    #[test]
+    fn orbit_circles_a_sinking_anchor() {
+        let orbit = Orbit { radius: 30.0, angular_speed: 0.3 };
+        let start = Vec2::new(20.0, 250.0);
+        let (motion, positions) = fly(&orbit, start, Vec2::default(), 100);
+        assert_close(motion.anchor_y, 250.0 - SPEED / 2.0 * STEP * 100.0);
+        for position in positions {
+            assert!(position.x <= start.x + 1e-9 && position.x >= start.x - 60.0 - 1e-9);
+        }
+    }
+
+    #[test]
+    fn kamikaze_turns_no_faster_than_its_turn_rate() {
+        let kamikaze = Kamikaze { turn_rate: 6.0 };
+        // Player far off to the side, so it has to turn a long way
+        let (motion, _) = fly(&kamikaze, Vec2::new(0.0, 250.0), Vec2::new(-120.0, 240.0), 5);
+        assert_close(motion.heading.angle(), -90.0 - 6.0 * STEP * 5.0);
+
+        let (motion, positions) = fly(&kamikaze, Vec2::new(0.0, 250.0), Vec2::new(0.0, 20.0), 200);
+        assert!(motion.leaving);
+        assert!(positions[199].y < 20.0);
+    }
+
+    #[test]
+    fn follow_path_mirrors_around_the_entry_x() {
+        let path = path::builtin().unwrap().get("loop").unwrap();
+        let plain = FollowPath { path: path.clone(), mirrored: false };
+        let mirrored = FollowPath { path, mirrored: true };
+
+        let (motion, positions) = fly(&plain, Vec2::new(10.0, 250.0), Vec2::default(), 30);
+        let (_, mirrored_positions) = fly(&mirrored, Vec2::new(10.0, 250.0), Vec2::default(), 30);
+        assert_close(motion.travelled, SPEED * STEP * 30.0);
+        for (position, mirrored_position) in positions.iter().zip(&mirrored_positions) {
+            assert_close(position.x - 10.0, -(mirrored_position.x - 10.0));
+            assert_close(position.y, mirrored_position.y);
+        }
+    }
+
+    #[test]
+    fn only_known_parameters_are_set() {
+        let mut sine = Behaviour::from_name("sine").unwrap();
+        assert!(sine.set_parameter("period", 5.0));
+        assert!(!sine.set_parameter("turn_rate", 5.0));
+        assert_eq!(sine, SineWeave { amplitude: 30.0, period: 5.0 });
+        let mut fly_across = FlyL2R;
+        assert!(!fly_across.set_parameter("period", 5.0));
+    }
+}
@@ -0,0 +1,102 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::renderer::Renderer;
+use flask::palette::FlaskColor;
+use flask::sprite::SpriteBank;
+
+use crate::controls::Controls;
+use crate::drawable::Drawable;
+use crate::entity::Entity;
+use crate::rng::Rng;
+use crate::vec2::Vec2;
+
+pub const BLAST_RADIUS: f64 = 90.0;
+const BLAST_TIME: f64 = 8.0;
+
+// Bomb shockwave. Grows to full size over its lifetime, wiping out enemy fire it sweeps over.
+#[derive(Clone)]
+pub struct Blast {
+    time: f64,
+    position: Vec2,
+}
+
+impl Blast {
+    pub fn new(position: Vec2) -> Blast {
+        Blast {
+            time: 0.0,
+            position,
+        }
+    }
+
+    pub fn alive(&self) -> bool {
+        self.time < BLAST_TIME
+    }
+
+    pub fn radius(&self) -> f64 {
+        // Fast at first, easing out toward full size
+        let progress = (self.time / BLAST_TIME).min(1.0);
+        BLAST_RADIUS * (1.0 - (1.0 - progress).powi(2))
+    }
+
+    pub fn covers(&self, entity: &dyn Entity) -> bool {
+        entity.position().distance(self.position) < self.radius()
+    }
+}
+
+impl Entity for Blast {
+    fn on_update(&mut self, _controls: &Controls, delta_time: f64, game_speed: f64) {
+        self.time += game_speed * delta_time;
+    }
+
+    fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    fn collider_radius(&self) -> i64 {
+        self.radius() as i64
+    }
+}
+
+impl Drawable for Blast {
+    fn on_draw(&self, renderer: &mut Renderer, _sprites: &mut SpriteBank, rng: &mut Rng) {
+        let (x, y) = self.position.rounded();
+        let radius = self.radius();
+
+        // Rings trailing the shockwave front, thinning out as it fades
+        let rings = [
+            (1.0, FlaskColor::White as u8),
+            (0.85, FlaskColor::Yellow as u8),
+            (0.65, FlaskColor::Red as u8),
+            (0.4, FlaskColor::Purple as u8),
+        ];
+        let shown = rings.len() - ((self.time / BLAST_TIME) * rings.len() as f64) as usize;
+        for (scale, color) in rings.iter().take(shown.max(1)) {
+            renderer.circle(x, y, (radius * scale) as u32, *color);
+        }
+
+        // Sparks thrown off the front
+        for _ in 0..12 {
+            let angle = rng.next_f64() * std::f64::consts::TAU;
+            let spark_x = x + (angle.cos() * radius) as i64;
+            let spark_y = y + (angle.sin() * radius) as i64;
+            renderer.circle_filled(spark_x, spark_y, 1, FlaskColor::Yellow as u8);
+        }
+    }
+}
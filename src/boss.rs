@@ -0,0 +1,386 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use std::f64::consts::TAU;
+
+use flask::renderer::Renderer;
+use flask::sprite::{SpriteBank, SpriteID};
+use flask::palette::FlaskColor;
+
+use crate::collider::Collider;
+use crate::drawable::Drawable;
+use crate::rng::Rng;
+use crate::vec2::Vec2;
+use crate::WINDOW_HEIGHT;
+
+// Boss body is built from enemy sprites, one per part
+const PART_SPRITE_ID: SpriteID = SpriteID(1);
+const ENTRY_SPEED: f64 = 2.0;
+const HOVER_Y: f64 = 200.0;
+// Degrees between the shots of one volley
+const SPREAD_ANGLE: f64 = 12.0;
+const SHOT_SPEED: f64 = 10.0;
+const PART_SCORE: u128 = 500;
+const DEFEAT_SCORE: u128 = 5000;
+const HIT_FLASH_TIME: f64 = 0.5;
+// Explosions walk over the body one after another before the wreck goes down
+const CHAIN_EXPLOSIONS: u32 = 18;
+const CHAIN_INTERVAL: f64 = 1.2;
+const SINK_SPEED: f64 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BossKind {
+    Gunship,
+    // Bigger, with armour plates shielding the core
+    Fortress,
+}
+
+impl BossKind {
+    pub const NAMES: [&'static str; 2] = ["gunship", "fortress"];
+
+    pub fn from_name(name: &str) -> Option<BossKind> {
+        match name {
+            "gunship" => Some(BossKind::Gunship),
+            "fortress" => Some(BossKind::Fortress),
+            _ => None,
+        }
+    }
+
+    fn parts(&self) -> Vec<Part> {
+        match self {
+            BossKind::Gunship => vec![
+                // Hull runs back from the core to the tail plate
+                Part::new(Role::Core, 0.0, 0.0, 10.0, 40).shaped(Collider::Compound(vec![
+                    (Vec2::ZERO, Collider::Circle { radius: 10.0 }),
+                    (Vec2::new(0.0, 8.0), Collider::Aabb { half_width: 3.0, half_height: 8.0 }),
+                ])),
+                Part::new(Role::Gun, -24.0, 4.0, 6.0, 15),
+                Part::new(Role::Gun, 24.0, 4.0, 6.0, 15),
+                Part::new(Role::Armour, 0.0, 16.0, 6.0, 10).shaped(Collider::Aabb { half_width: 10.0, half_height: 3.0 }),
+            ],
+            BossKind::Fortress => vec![
+                Part::new(Role::Core, 0.0, 0.0, 12.0, 60),
+                // Slanted plates under the core
+                Part::new(Role::Armour, -16.0, -10.0, 7.0, 20).shaped(Collider::Obb { half_width: 9.0, half_height: 3.0, axis: Vec2::from_angle(-30.0) }),
+                Part::new(Role::Armour, 16.0, -10.0, 7.0, 20).shaped(Collider::Obb { half_width: 9.0, half_height: 3.0, axis: Vec2::from_angle(30.0) }),
+                Part::new(Role::Gun, -36.0, 0.0, 6.0, 12),
+                Part::new(Role::Gun, 36.0, 0.0, 6.0, 12),
+                Part::new(Role::Gun, -18.0, 14.0, 6.0, 12),
+                Part::new(Role::Gun, 18.0, 14.0, 6.0, 12),
+            ],
+        }
+    }
+
+    fn phases(&self) -> &'static [Phase] {
+        match self {
+            BossKind::Gunship => &[
+                Phase { until: 0.6, strafe_width: 50.0, strafe_period: 60.0, fire_interval: 12.0, volley: 1, core_fires: false },
+                Phase { until: 0.3, strafe_width: 70.0, strafe_period: 40.0, fire_interval: 9.0, volley: 3, core_fires: false },
+                Phase { until: 0.0, strafe_width: 90.0, strafe_period: 30.0, fire_interval: 6.0, volley: 3, core_fires: true },
+            ],
+            BossKind::Fortress => &[
+                Phase { until: 0.7, strafe_width: 30.0, strafe_period: 80.0, fire_interval: 14.0, volley: 1, core_fires: false },
+                Phase { until: 0.4, strafe_width: 50.0, strafe_period: 60.0, fire_interval: 10.0, volley: 3, core_fires: true },
+                Phase { until: 0.0, strafe_width: 70.0, strafe_period: 40.0, fire_interval: 7.0, volley: 5, core_fires: true },
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Role {
+    // Destroying it defeats the boss
+    Core,
+    Gun,
+    // Only soaks up shots
+    Armour,
+}
+
+#[derive(Clone, Debug)]
+struct Part {
+    role: Role,
+    // Relative to the boss position
+    offset: Vec2,
+    // Roughly how big it looks, the collider has the exact shape
+    radius: f64,
+    collider: Collider,
+    health: u32,
+    max_health: u32,
+    shoot_timer: f64,
+}
+
+impl Part {
+    fn new(role: Role, offset_x: f64, offset_y: f64, radius: f64, health: u32) -> Part {
+        Part {
+            role,
+            offset: Vec2::new(offset_x, offset_y),
+            radius,
+            collider: Collider::Circle { radius },
+            health,
+            max_health: health,
+            // Guns on either side fire out of step
+            shoot_timer: 5.0 + offset_x.abs() / 4.0,
+        }
+    }
+
+    fn shaped(self, collider: Collider) -> Part {
+        Part {
+            collider,
+            ..self
+        }
+    }
+
+    fn alive(&self) -> bool {
+        self.health > 0
+    }
+}
+
+// How the boss fights while its remaining health is above `until`, as a fraction of full health
+#[derive(Clone, Copy, Debug)]
+struct Phase {
+    until: f64,
+    strafe_width: f64,
+    strafe_period: f64,
+    fire_interval: f64,
+    // Shots per volley, fanned out around the aimed one
+    volley: u32,
+    core_fires: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum State {
+    Entering,
+    Fighting,
+    Dying { chain: u32, timer: f64 },
+    Done,
+}
+
+// Things the world has to act on after a boss update
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BossEvent {
+    Shot { position: Vec2, velocity: Vec2 },
+    Explosion { position: Vec2 },
+}
+
+#[derive(Clone)]
+pub struct Boss {
+    kind: BossKind,
+    position: Vec2,
+    parts: Vec<Part>,
+    phase: usize,
+    strafe_angle: f64,
+    state: State,
+    hit_flash: f64,
+}
+
+impl Boss {
+    pub fn new(kind: BossKind) -> Boss {
+        Boss {
+            kind,
+            position: Vec2::new(0.0, WINDOW_HEIGHT as f64 + 40.0),
+            parts: kind.parts(),
+            phase: 0,
+            strafe_angle: 0.0,
+            state: State::Entering,
+            hit_flash: 0.0,
+        }
+    }
+
+    // Remaining health of all parts as a fraction of full health
+    pub fn health_fraction(&self) -> f64 {
+        let health: u32 = self.parts.iter().map(|part| part.health).sum();
+        let max_health: u32 = self.parts.iter().map(|part| part.max_health).sum();
+        health as f64 / max_health as f64
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        matches!(self.state, State::Dying { .. } | State::Done)
+    }
+
+    // Chained explosions are over, the boss can be removed
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    pub fn update(&mut self, delta_time: f64, game_speed: f64, target: Vec2) -> Vec<BossEvent> {
+        let step = game_speed * delta_time;
+        self.hit_flash -= step;
+
+        let mut events = vec![];
+        match self.state {
+            State::Entering => {
+                self.position.y -= ENTRY_SPEED * step;
+                if self.position.y <= HOVER_Y {
+                    self.position.y = HOVER_Y;
+                    self.state = State::Fighting;
+                }
+            }
+            State::Fighting => {
+                let phase = self.kind.phases()[self.phase];
+                self.strafe_angle += TAU * step / phase.strafe_period;
+                self.position.x = phase.strafe_width * self.strafe_angle.sin();
+
+                for part in &mut self.parts {
+                    let fires = match part.role {
+                        Role::Gun => true,
+                        Role::Core => phase.core_fires,
+                        Role::Armour => false,
+                    };
+                    if !part.alive() || !fires {
+                        continue;
+                    }
+
+                    part.shoot_timer -= step;
+                    if part.shoot_timer < 0.0 {
+                        part.shoot_timer = phase.fire_interval;
+
+                        let position = self.position + part.offset;
+                        let aim = (target - position).normalised();
+                        for shot in 0..phase.volley {
+                            let turn = (shot as f64 - (phase.volley - 1) as f64 / 2.0) * SPREAD_ANGLE;
+                            events.push(BossEvent::Shot { position, velocity: aim.rotate(turn) * SHOT_SPEED });
+                        }
+                    }
+                }
+            }
+            State::Dying { chain, timer } => {
+                self.position.y -= SINK_SPEED * step;
+
+                let timer = timer - step;
+                if timer > 0.0 {
+                    self.state = State::Dying { chain, timer };
+                } else if chain < CHAIN_EXPLOSIONS {
+                    // Walk over the parts, wobbling a little so repeats don't land on the same spot
+                    let part = &self.parts[chain as usize % self.parts.len()];
+                    let wobble = ((chain * 7) % 11) as f64 - 5.0;
+                    events.push(BossEvent::Explosion { position: self.position + part.offset + Vec2::new(wobble, -wobble / 2.0) });
+                    self.state = State::Dying { chain: chain + 1, timer: CHAIN_INTERVAL };
+                } else {
+                    // Final blast over the whole body
+                    for part in &self.parts {
+                        events.push(BossEvent::Explosion { position: self.position + part.offset });
+                    }
+                    self.state = State::Done;
+                }
+            }
+            State::Done => {}
+        }
+
+        events
+    }
+
+    // Shots only land while it's fighting, before that it just flies in
+    pub fn vulnerable(&self) -> bool {
+        self.state == State::Fighting
+    }
+
+    // Index, position and collider of every part that can still be run into
+    pub fn solid_parts(&self) -> Vec<(usize, Vec2, Collider)> {
+        if self.is_defeated() {
+            return vec![];
+        }
+        (0..self.parts.len())
+            .filter(|index| self.parts[*index].alive())
+            .map(|index| (index, self.part_position(index), self.parts[index].collider.clone()))
+            .collect()
+    }
+
+    // Parts worth aiming at
+    pub fn targets(&self) -> Vec<Vec2> {
+        if self.is_defeated() {
+            return vec![];
+        }
+        (0..self.parts.len()).filter(|index| self.parts[*index].alive()).map(|index| self.part_position(index)).collect()
+    }
+
+    // Parts within `radius` of a point that can be hurt right now
+    pub fn parts_within(&self, center: Vec2, radius: f64) -> Vec<usize> {
+        if self.state != State::Fighting {
+            return vec![];
+        }
+        (0..self.parts.len())
+            .filter(|index| {
+                self.parts[*index].alive() && self.part_position(*index).distance(center) < radius + self.parts[*index].radius
+            })
+            .collect()
+    }
+
+    pub fn part_position(&self, index: usize) -> Vec2 {
+        self.position + self.parts[index].offset
+    }
+
+    // Returns score earned when this destroys the part. Losing the core defeats the boss.
+    pub fn damage(&mut self, index: usize, amount: u8) -> Option<u128> {
+        let part = &mut self.parts[index];
+        if !part.alive() {
+            return None;
+        }
+
+        part.health = part.health.saturating_sub(amount as u32);
+        self.hit_flash = HIT_FLASH_TIME;
+
+        let fraction = self.health_fraction();
+        let phases = self.kind.phases();
+        while self.phase + 1 < phases.len() && fraction <= phases[self.phase].until {
+            self.phase += 1;
+        }
+
+        let part = &self.parts[index];
+        if part.alive() {
+            return None;
+        }
+        if part.role == Role::Core {
+            self.state = State::Dying { chain: 0, timer: 0.0 };
+            return Some(DEFEAT_SCORE);
+        }
+        Some(PART_SCORE)
+    }
+}
+
+impl Drawable for Boss {
+    fn on_draw(&self, renderer: &mut Renderer, sprites: &mut SpriteBank, rng: &mut Rng) {
+        if self.state == State::Done {
+            return;
+        }
+
+        let sprite = sprites.get_sprite(&PART_SPRITE_ID).unwrap();
+        let x_offset = (sprite.get_width() / 2) as i64;
+        let y_offset = (sprite.get_height() / 2) as i64;
+
+        for part in &self.parts {
+            let (x, y) = (self.position + part.offset).rounded();
+
+            if !part.alive() {
+                // Burning wreckage
+                renderer.circle_filled(x, y, part.radius as u32 / 2, FlaskColor::Red as u8);
+                renderer.circle_filled(x + rng.next_i64_in_range(-2, 3), y + rng.next_i64_in_range(-2, 3), 1, FlaskColor::Yellow as u8);
+                continue;
+            }
+
+            if part.role == Role::Core {
+                renderer.circle_filled(x, y, part.radius as u32, FlaskColor::Purple as u8);
+            }
+            renderer.sprite(sprite, x - x_offset, y - y_offset, false);
+
+            if self.hit_flash > 0.0 {
+                renderer.circle(x, y, part.radius as u32, FlaskColor::White as u8);
+            }
+        }
+    }
+}
@@ -17,51 +17,56 @@
 // along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
 //
 
-use flask::game_status::GameStatus;
-use flask::rand::Rand;
 use flask::renderer::Renderer;
-use flask::input::Input;
 use flask::palette::FlaskColor;
 use flask::sprite::SpriteBank;
 
+use crate::controls::Controls;
+use crate::drawable::Drawable;
 use crate::entity::Entity;
-use crate::game::{WALK_AREA_MAX_X, WALK_AREA_MAX_Y};
+use crate::world::{WALK_AREA_MAX_X, WALK_AREA_MAX_Y};
+use crate::rng::Rng;
+use crate::vec2::Vec2;
 
 const MAX_OFFSET_X: i64 = 30;
 const MAX_OFFSET_Y: i64 = 10;
 const PILLOW_SIZE: u32 = 10;
 
+#[derive(Clone)]
 struct CloudPillow {
-    position_x: f64,
-    position_y: f64,
+    offset: Vec2,
 }
 
 impl CloudPillow {
-    pub fn new(rng: &mut Rand) -> CloudPillow {
+    pub fn new(rng: &mut Rng) -> CloudPillow {
         CloudPillow {
-            position_x: rng.next_i64_in_range(-MAX_OFFSET_X, MAX_OFFSET_X) as f64,
-            position_y: rng.next_i64_in_range(-MAX_OFFSET_Y, MAX_OFFSET_Y) as f64,
+            offset: Vec2::new(
+                rng.next_i64_in_range(-MAX_OFFSET_X, MAX_OFFSET_X) as f64,
+                rng.next_i64_in_range(-MAX_OFFSET_Y, MAX_OFFSET_Y) as f64,
+            ),
         }
     }
 }
 
+#[derive(Clone)]
 pub struct Cloud {
-    position_x: f64,
-    position_y: f64,
+    position: Vec2,
     speed: f64,
     cloud_pillows: Vec<CloudPillow>,
 }
 
 impl Cloud {
-    pub fn new(rng: &mut Rand) -> Cloud {
+    pub fn new(rng: &mut Rng) -> Cloud {
         let mut cloud_pillows = vec![];
         for _ in 0..rng.next_i64_in_range(20, 30) {
             cloud_pillows.push(CloudPillow::new(rng))
         }
 
         Cloud {
-            position_x: rng.next_i64_in_range(-WALK_AREA_MAX_X as i64, WALK_AREA_MAX_X as i64) as f64,
-            position_y: rng.next_i64_in_range(0, WALK_AREA_MAX_Y as i64 + (MAX_OFFSET_Y + PILLOW_SIZE as i64)) as f64,
+            position: Vec2::new(
+                rng.next_i64_in_range(-WALK_AREA_MAX_X as i64, WALK_AREA_MAX_X as i64) as f64,
+                rng.next_i64_in_range(0, WALK_AREA_MAX_Y as i64 + (MAX_OFFSET_Y + PILLOW_SIZE as i64)) as f64,
+            ),
             speed: rng.next_i64_in_range(2, 5) as f64,
             cloud_pillows
         }
@@ -69,27 +74,28 @@ impl Cloud {
 }
 
 impl Entity for Cloud {
-    fn on_update(&mut self, _game_status: &mut GameStatus, renderer: &mut Renderer, _sprites: &mut SpriteBank, _input: &Input, delta_time: f64, game_speed: f64) {
-        self.position_y -= self.speed * game_speed * delta_time;
+    fn on_update(&mut self, _controls: &Controls, delta_time: f64, game_speed: f64) {
+        self.position.y -= self.speed * game_speed * delta_time;
 
-        if self.position_y + ((MAX_OFFSET_Y + PILLOW_SIZE as i64) as f64) < 0.0 {
-            self.position_y = WALK_AREA_MAX_Y + (MAX_OFFSET_Y + PILLOW_SIZE as i64) as f64;
+        if self.position.y + ((MAX_OFFSET_Y + PILLOW_SIZE as i64) as f64) < 0.0 {
+            self.position.y = WALK_AREA_MAX_Y + (MAX_OFFSET_Y + PILLOW_SIZE as i64) as f64;
         }
-
-        for pillow in &mut self.cloud_pillows {
-            renderer.circle_filled(self.position_x as i64 + pillow.position_x as i64, self.position_y as i64 + pillow.position_y as i64, PILLOW_SIZE, FlaskColor::White as u8);
-        }
-    }
-
-    fn x(&self) -> i64 {
-        self.position_x as i64
     }
 
-    fn y(&self) -> i64 {
-        self.position_y as i64
+    fn position(&self) -> Vec2 {
+        self.position
     }
 
     fn collider_radius(&self) -> i64 {
         0
     }
+}
+
+impl Drawable for Cloud {
+    fn on_draw(&self, renderer: &mut Renderer, _sprites: &mut SpriteBank, _rng: &mut Rng) {
+        for pillow in &self.cloud_pillows {
+            let (x, y) = (self.position + pillow.offset).rounded();
+            renderer.circle_filled(x, y, PILLOW_SIZE, FlaskColor::White as u8);
+        }
+    }
 }
\ No newline at end of file
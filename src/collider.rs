@@ -0,0 +1,267 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use crate::vec2::Vec2;
+
+// Collision shape, placed relative to the owner's position
+#[derive(Clone, PartialEq, Debug)]
+pub enum Collider {
+    Circle { radius: f64 },
+    // Axis-aligned box
+    Aabb { half_width: f64, half_height: f64 },
+    // Box turned so its width runs along `axis`, a unit vector
+    Obb { half_width: f64, half_height: f64, axis: Vec2 },
+    // Segment from start to end, thickened by radius. Zero radius is a plain segment.
+    Capsule { start: Vec2, end: Vec2, radius: f64 },
+    // Several shapes, each at an offset from the owner
+    Compound(Vec<(Vec2, Collider)>),
+}
+
+impl Collider {
+    pub fn overlaps(&self, position: Vec2, other: &Collider, other_position: Vec2) -> bool {
+        let ours = self.primitives(position);
+        let theirs = other.primitives(other_position);
+        ours.iter().any(|a| theirs.iter().any(|b| a.overlaps(b)))
+    }
+
+    // Radius of a circle around the owner's position that holds the whole shape, for the broadphase
+    pub fn bounding_radius(&self) -> f64 {
+        match self {
+            Collider::Circle { radius } => *radius,
+            Collider::Aabb { half_width, half_height } | Collider::Obb { half_width, half_height, .. } => {
+                Vec2::new(*half_width, *half_height).length()
+            }
+            Collider::Capsule { start, end, radius } => start.length().max(end.length()) + radius,
+            Collider::Compound(parts) => parts
+                .iter()
+                .map(|(offset, part)| offset.length() + part.bounding_radius())
+                .fold(0.0, f64::max),
+        }
+    }
+
+    // Circles are capsules with both ends together and axis-aligned boxes are boxes with no turn,
+    // so only three kinds of pairs need testing
+    fn primitives(&self, position: Vec2) -> Vec<Primitive> {
+        match self {
+            Collider::Circle { radius } => vec![Primitive::Capsule { start: position, end: position, radius: *radius }],
+            Collider::Aabb { half_width, half_height } => vec![Primitive::Box {
+                center: position,
+                axes: [Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)],
+                half: (*half_width, *half_height),
+            }],
+            Collider::Obb { half_width, half_height, axis } => vec![Primitive::Box {
+                center: position,
+                axes: [*axis, axis.perpendicular()],
+                half: (*half_width, *half_height),
+            }],
+            Collider::Capsule { start, end, radius } => vec![Primitive::Capsule {
+                start: position + *start,
+                end: position + *end,
+                radius: *radius,
+            }],
+            Collider::Compound(parts) => parts
+                .iter()
+                .flat_map(|(offset, part)| part.primitives(position + *offset))
+                .collect(),
+        }
+    }
+}
+
+enum Primitive {
+    Capsule { start: Vec2, end: Vec2, radius: f64 },
+    // Axes are unit vectors along the box's width and height
+    Box { center: Vec2, axes: [Vec2; 2], half: (f64, f64) },
+}
+
+impl Primitive {
+    fn overlaps(&self, other: &Primitive) -> bool {
+        match (self, other) {
+            (Primitive::Capsule { start, end, radius }, Primitive::Capsule { start: other_start, end: other_end, radius: other_radius }) => {
+                within(segment_distance((*start, *end), (*other_start, *other_end)), radius + other_radius)
+            }
+            (Primitive::Capsule { start, end, radius }, Primitive::Box { .. }) => within(box_distance(other, (*start, *end)), *radius),
+            (Primitive::Box { .. }, Primitive::Capsule { start, end, radius }) => within(box_distance(self, (*start, *end)), *radius),
+            (Primitive::Box { .. }, Primitive::Box { .. }) => boxes_overlap(self, other),
+        }
+    }
+
+    fn corners(&self) -> Vec<Vec2> {
+        match self {
+            Primitive::Capsule { start, end, .. } => vec![*start, *end],
+            Primitive::Box { center, axes, half } => {
+                let u = axes[0] * half.0;
+                let v = axes[1] * half.1;
+                vec![*center + u + v, *center - u + v, *center - u - v, *center + u - v]
+            }
+        }
+    }
+}
+
+// Touching edges don't count, same as circles always worked, but actually meeting always does,
+// so zero radius segments hit whatever they pass through
+fn within(distance: f64, radius: f64) -> bool {
+    distance <= 0.0 || distance < radius
+}
+
+// Separating axis test, the only axes worth trying are the edge normals of both boxes
+fn boxes_overlap(a: &Primitive, b: &Primitive) -> bool {
+    let (a_corners, b_corners) = (a.corners(), b.corners());
+    let axes = match (a, b) {
+        (Primitive::Box { axes: a_axes, .. }, Primitive::Box { axes: b_axes, .. }) => [a_axes[0], a_axes[1], b_axes[0], b_axes[1]],
+        _ => return false,
+    };
+    axes.iter().all(|axis| {
+        let (a_min, a_max) = project(&a_corners, *axis);
+        let (b_min, b_max) = project(&b_corners, *axis);
+        a_min < b_max && b_min < a_max
+    })
+}
+
+// Zero if the segment reaches into the box. Otherwise the closest pair has an end of the segment
+// or a corner of the box in it.
+fn box_distance(shape: &Primitive, segment: (Vec2, Vec2)) -> f64 {
+    let (center, axes, half) = match shape {
+        Primitive::Box { center, axes, half } => (*center, *axes, *half),
+        _ => return f64::INFINITY,
+    };
+
+    // Into box space, where it is axis-aligned around the origin
+    let local = |point: Vec2| {
+        let relative = point - center;
+        Vec2::new(relative.dot(axes[0]), relative.dot(axes[1]))
+    };
+    let (start, end) = (local(segment.0), local(segment.1));
+    if clip_segment(start, end, half) {
+        return 0.0;
+    }
+
+    let point_distance = |point: Vec2| Vec2::new((point.x.abs() - half.0).max(0.0), (point.y.abs() - half.1).max(0.0)).length();
+    let mut distance = point_distance(start).min(point_distance(end));
+    for corner in shape.corners() {
+        distance = distance.min(point_segment_distance(corner, segment));
+    }
+    distance
+}
+
+// Whether a box-space segment passes through the inside of the box, Liang-Barsky style
+fn clip_segment(start: Vec2, end: Vec2, half: (f64, f64)) -> bool {
+    let direction = end - start;
+    let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+    for (position, delta, limit) in [(start.x, direction.x, half.0), (start.y, direction.y, half.1)] {
+        if delta == 0.0 {
+            if position.abs() >= limit {
+                return false;
+            }
+            continue;
+        }
+        let near = (-limit - position) / delta;
+        let far = (limit - position) / delta;
+        enter = enter.max(near.min(far));
+        exit = exit.min(near.max(far));
+    }
+    enter < exit
+}
+
+fn segment_distance(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> f64 {
+    if segments_cross(a, b) {
+        return 0.0;
+    }
+    point_segment_distance(a.0, b)
+        .min(point_segment_distance(a.1, b))
+        .min(point_segment_distance(b.0, a))
+        .min(point_segment_distance(b.1, a))
+}
+
+fn segments_cross(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> bool {
+    let side = |p: Vec2, q: Vec2, r: Vec2| (q - p).cross(r - p);
+    let (d1, d2) = (side(b.0, b.1, a.0), side(b.0, b.1, a.1));
+    let (d3, d4) = (side(a.0, a.1, b.0), side(a.0, a.1, b.1));
+    ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+}
+
+fn point_segment_distance(point: Vec2, segment: (Vec2, Vec2)) -> f64 {
+    let direction = segment.1 - segment.0;
+    let length_squared = direction.length_squared();
+    let t = match length_squared == 0.0 {
+        true => 0.0,
+        false => ((point - segment.0).dot(direction) / length_squared).clamp(0.0, 1.0),
+    };
+    point.distance(segment.0.lerp(segment.1, t))
+}
+
+fn project(points: &[Vec2], axis: Vec2) -> (f64, f64) {
+    points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), point| {
+        let value = point.dot(axis);
+        (min.min(value), max.max(value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(x1: f64, y1: f64, x2: f64, y2: f64) -> Collider {
+        Collider::Capsule { start: Vec2::new(x1, y1), end: Vec2::new(x2, y2), radius: 0.0 }
+    }
+
+    #[test]
+    fn circles_touching_edges_dont_overlap() {
+        let circle = Collider::Circle { radius: 5.0 };
+        assert!(circle.overlaps(Vec2::ZERO, &circle, Vec2::new(9.9, 0.0)));
+        assert!(!circle.overlaps(Vec2::ZERO, &circle, Vec2::new(10.0, 0.0)));
+    }
+
+    #[test]
+    fn zero_radius_segments_hit_what_they_cross() {
+        let across = segment(-10.0, 0.0, 10.0, 0.0);
+        let down = segment(0.0, 10.0, 0.0, -10.0);
+        assert!(across.overlaps(Vec2::ZERO, &down, Vec2::ZERO));
+        assert!(!across.overlaps(Vec2::ZERO, &down, Vec2::new(20.0, 0.0)));
+
+        let wall = Collider::Aabb { half_width: 2.0, half_height: 2.0 };
+        assert!(across.overlaps(Vec2::ZERO, &wall, Vec2::ZERO));
+        assert!(wall.overlaps(Vec2::new(5.0, 0.0), &across, Vec2::ZERO));
+        assert!(!across.overlaps(Vec2::ZERO, &wall, Vec2::new(0.0, 5.0)));
+    }
+
+    #[test]
+    fn turned_boxes_overlap_only_where_they_reach() {
+        let plank = Collider::Obb { half_width: 10.0, half_height: 1.0, axis: Vec2::from_angle(45.0) };
+        let dot = Collider::Circle { radius: 1.0 };
+        assert!(plank.overlaps(Vec2::ZERO, &dot, Vec2::new(5.0, 5.0)));
+        // Inside the unturned plank, but well off the diagonal one
+        assert!(!plank.overlaps(Vec2::ZERO, &dot, Vec2::new(8.0, 0.0)));
+
+        let square = Collider::Aabb { half_width: 3.0, half_height: 3.0 };
+        assert!(plank.overlaps(Vec2::ZERO, &square, Vec2::new(6.0, 6.0)));
+        assert!(!plank.overlaps(Vec2::ZERO, &square, Vec2::new(-6.0, 6.0)));
+    }
+
+    #[test]
+    fn compound_parts_are_placed_at_their_offsets() {
+        let body = Collider::Compound(vec![
+            (Vec2::ZERO, Collider::Circle { radius: 10.0 }),
+            (Vec2::new(0.0, 20.0), Collider::Aabb { half_width: 3.0, half_height: 8.0 }),
+        ]);
+        let dot = Collider::Circle { radius: 1.0 };
+        assert!(body.overlaps(Vec2::ZERO, &dot, Vec2::new(0.0, 26.0)));
+        assert!(!body.overlaps(Vec2::ZERO, &dot, Vec2::new(8.0, 20.0)));
+        assert_eq!(body.bounding_radius(), 20.0 + Vec2::new(3.0, 8.0).length());
+    }
+}
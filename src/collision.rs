@@ -0,0 +1,192 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use crate::collider::Collider;
+use crate::entity::Entity;
+use crate::grid::{SpatialGrid, CELL_SIZE};
+use crate::vec2::Vec2;
+
+// What kind of thing a collider is. Masks say which layers a collider reacts to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layer {
+    Player,
+    PlayerBullet,
+    Enemy,
+    EnemyBullet,
+    Pickup,
+    // Neutral obstacles that get in everyone's way
+    Hazard,
+}
+
+impl Layer {
+    fn bit(&self) -> u8 {
+        match self {
+            Layer::Player => 1,
+            Layer::PlayerBullet => 1 << 1,
+            Layer::Enemy => 1 << 2,
+            Layer::EnemyBullet => 1 << 3,
+            Layer::Pickup => 1 << 4,
+            Layer::Hazard => 1 << 5,
+        }
+    }
+
+    // Who hits whom unless an entity says otherwise. Only one side of a pair needs the other in its mask.
+    pub fn default_mask(&self) -> Mask {
+        match self {
+            Layer::Player => Mask::NONE,
+            Layer::PlayerBullet => Mask::of(&[Layer::Enemy]),
+            Layer::Enemy => Mask::NONE,
+            Layer::EnemyBullet => Mask::of(&[Layer::Player]),
+            Layer::Pickup => Mask::of(&[Layer::Player]),
+            Layer::Hazard => Mask::of(&[Layer::Player, Layer::Enemy]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Mask(u8);
+
+impl Mask {
+    pub const NONE: Mask = Mask(0);
+
+    pub fn of(layers: &[Layer]) -> Mask {
+        Mask(layers.iter().fold(0, |bits, layer| bits | layer.bit()))
+    }
+
+    pub fn contains(&self, layer: Layer) -> bool {
+        self.0 & layer.bit() != 0
+    }
+}
+
+// Where a collider came from in the world, so hits can be acted on
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Handle {
+    Player,
+    Enemy(usize),
+    BossPart(usize),
+    EnemyBullet(usize),
+    PlayerBullet(usize),
+    Pickup(usize),
+}
+
+// Source had the target's layer in its mask and the two overlap
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Hit {
+    pub source: Handle,
+    pub target: Handle,
+}
+
+#[derive(Clone, Debug)]
+struct Body {
+    handle: Handle,
+    layer: Layer,
+    mask: Mask,
+    position: Vec2,
+    collider: Collider,
+    // Of a circle holding the whole collider, for the grid
+    reach: i64,
+    // Where it was at the start of the update, so hits can be put in the order they happened
+    origin: Vec2,
+}
+
+impl Body {
+    // How far along its last move, 0 to 1, this body got closest to the point
+    fn time_to(&self, point: Vec2) -> f64 {
+        let travelled = self.position - self.origin;
+        let length_squared = travelled.length_squared();
+        if length_squared == 0.0 {
+            return 0.0;
+        }
+        ((point - self.origin).dot(travelled) / length_squared).clamp(0.0, 1.0)
+    }
+}
+
+// Everything collidable for one update. Filled every update, then hits() does the one pass over it.
+#[derive(Clone)]
+pub struct Collisions {
+    bodies: Vec<Body>,
+    grid: SpatialGrid,
+}
+
+impl Collisions {
+    pub fn new() -> Collisions {
+        Collisions {
+            bodies: vec![],
+            grid: SpatialGrid::new(CELL_SIZE),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+        self.grid.clear();
+    }
+
+    // Entities without a layer don't collide and are left out
+    pub fn add(&mut self, handle: Handle, entity: &dyn Entity) {
+        if let Some(layer) = entity.layer() {
+            let position = entity.position();
+            self.add_collider(handle, layer, entity.mask(), position, entity.collider());
+            self.bodies.last_mut().unwrap().origin = position - entity.last_move();
+        }
+    }
+
+    // For colliders that aren't entities of their own, like boss parts
+    pub fn add_collider(&mut self, handle: Handle, layer: Layer, mask: Mask, position: Vec2, collider: Collider) {
+        let index = self.bodies.len();
+        // Grid works in whole pixels, pad so rounding never drops a candidate
+        let reach = collider.bounding_radius().ceil() as i64 + 1;
+        let (x, y) = position.rounded();
+        self.grid.insert_area(index, x, y, reach);
+        self.bodies.push(Body { handle, layer, mask, position, collider, reach, origin: position });
+    }
+
+    // All hits this update, grouped by source in the order bodies were added. A source's own hits
+    // come earliest first along its last move, so a fast shot is spent on the first thing in its way.
+    pub fn hits(&self) -> Vec<Hit> {
+        let mut hits = vec![];
+        for (i, body) in self.bodies.iter().enumerate() {
+            let (x, y) = body.position.rounded();
+            for j in self.grid.query_area(x, y, body.reach) {
+                if j <= i {
+                    continue;
+                }
+                let other = &self.bodies[j];
+                let forward = body.mask.contains(other.layer);
+                let backward = other.mask.contains(body.layer);
+                if !(forward || backward) || !body.collider.overlaps(body.position, &other.collider, other.position) {
+                    continue;
+                }
+                if forward {
+                    hits.push((i, body.time_to(other.position), Hit { source: body.handle, target: other.handle }));
+                }
+                if backward {
+                    hits.push((j, other.time_to(body.position), Hit { source: other.handle, target: body.handle }));
+                }
+            }
+        }
+        hits.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        hits.into_iter().map(|(_, _, hit)| hit).collect()
+    }
+}
+
+impl Default for Collisions {
+    fn default() -> Collisions {
+        Collisions::new()
+    }
+}
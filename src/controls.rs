@@ -0,0 +1,69 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::input::{Input, Key, State};
+
+// Snapshot of the keys gameplay cares about, taken once per frame so the simulation doesn't need flask input
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Controls {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub escape: bool,
+    pub enter: bool,
+}
+
+impl Controls {
+    pub fn from_input(input: &Input) -> Controls {
+        Controls {
+            up: input.get_key_state(Key::W) == State::Down,
+            down: input.get_key_state(Key::S) == State::Down,
+            left: input.get_key_state(Key::A) == State::Down,
+            right: input.get_key_state(Key::D) == State::Down,
+            escape: input.get_key_state(Key::Escape) == State::Down,
+            enter: input.get_key_state(Key::Enter) == State::Down,
+        }
+    }
+
+    // Keys that went down since the previous snapshot, for menus that react once per key press
+    pub fn pressed(&self, previous: &Controls) -> Controls {
+        Controls::from_bits(self.to_bits() & !previous.to_bits())
+    }
+
+    pub fn to_bits(&self) -> u8 {
+        (self.up as u8)
+            | (self.down as u8) << 1
+            | (self.left as u8) << 2
+            | (self.right as u8) << 3
+            | (self.escape as u8) << 4
+            | (self.enter as u8) << 5
+    }
+
+    pub fn from_bits(bits: u8) -> Controls {
+        Controls {
+            up: bits & 1 != 0,
+            down: bits & (1 << 1) != 0,
+            left: bits & (1 << 2) != 0,
+            right: bits & (1 << 3) != 0,
+            escape: bits & (1 << 4) != 0,
+            enter: bits & (1 << 5) != 0,
+        }
+    }
+}
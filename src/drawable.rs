@@ -0,0 +1,28 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::renderer::Renderer;
+use flask::sprite::SpriteBank;
+
+use crate::rng::Rng;
+
+pub trait Drawable {
+    // Cosmetic randomness comes from the caller so drawing never touches gameplay state
+    fn on_draw(&self, renderer: &mut Renderer, sprites: &mut SpriteBank, rng: &mut Rng);
+}
@@ -17,130 +17,314 @@
 // along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
 //
 
-use flask::game_status::GameStatus;
-use flask::rand::Rand;
 use flask::renderer::Renderer;
-use flask::input::Input;
 use flask::sprite::{SpriteBank, SpriteID};
 use flask::palette::FlaskColor;
 
+use crate::collision::{Layer, Mask};
+use crate::controls::Controls;
+use crate::drawable::Drawable;
 use crate::entity::Entity;
-use crate::game::WALK_AREA_MAX_X;
+use crate::aim::{Aim, Target};
+use crate::behaviour::{Behaviour, Motion};
+use crate::pattern::{Bullet, Emitter, Pattern};
+use crate::pickup::{DropTable, PickupKind};
+use crate::weapon::WeaponKind;
+use crate::world::WALK_AREA_MAX_X;
 use crate::WINDOW_HEIGHT;
-use crate::enemy::Behaviour::{FlyL2R, FlyR2L};
+use crate::rng::Rng;
+use crate::vec2::Vec2;
 
 const SHOOT_INTERVAL: f64 = 10.0;
+const SPRITE_ID: SpriteID = SpriteID(1);
 
+#[derive(Clone)]
 pub struct Enemy {
+    kind: EnemyKind,
     health: u8,
     speed: f64,
-    position_x: f64,
-    position_y: f64,
+    position: Vec2,
     behaviour: Behaviour,
-    shoot_timer: f64,
-    sprite_id: SpriteID,
-    rng: Rand,
+    motion: Motion,
+    // Player position, for behaviours that chase them
+    target: Vec2,
+    // None for enemies that don't shoot
+    emitter: Option<Emitter>,
+    squadron: Option<u32>,
+    leader: bool,
+    // Wingman position relative to the leader while holding formation
+    formation_offset: Option<Vec2>,
+    // Where a wingman flies relative to the path once its leader got away
+    path_offset: Vec2,
 }
 
-enum Behaviour {
-    FlyL2R,
-    FlyR2L,
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnemyKind {
+    Fighter,
+    // Faster but fragile
+    Scout,
+}
+
+impl EnemyKind {
+    pub const NAMES: [&'static str; 2] = ["fighter", "scout"];
+
+    pub fn from_name(name: &str) -> Option<EnemyKind> {
+        match name {
+            "fighter" => Some(EnemyKind::Fighter),
+            "scout" => Some(EnemyKind::Scout),
+            _ => None,
+        }
+    }
+
+    fn health(&self) -> u8 {
+        match self {
+            EnemyKind::Fighter => 5,
+            EnemyKind::Scout => 2,
+        }
+    }
+
+    fn speed(&self) -> f64 {
+        match self {
+            EnemyKind::Fighter => 5.0,
+            EnemyKind::Scout => 8.0,
+        }
+    }
+
+    // Gunnery of waves that ask for "lead": fighters lead their shots, scouts just spray in the player's general direction
+    pub fn aim(&self) -> Aim {
+        match self {
+            EnemyKind::Fighter => Aim { lead: 0.6, error: 3.0 },
+            EnemyKind::Scout => Aim { lead: 0.0, error: 8.0 },
+        }
+    }
+
+    pub fn drops(&self) -> DropTable {
+        match self {
+            EnemyKind::Fighter => DropTable {
+                chance: 0.25,
+                weights: &[
+                    (PickupKind::Medal, 5),
+                    (PickupKind::Weapon(WeaponKind::Twin), 1),
+                    (PickupKind::Weapon(WeaponKind::Spread), 1),
+                    (PickupKind::Weapon(WeaponKind::Laser), 1),
+                    (PickupKind::Weapon(WeaponKind::Homing), 1),
+                    (PickupKind::Shield, 2),
+                    (PickupKind::Bomb, 1),
+                    (PickupKind::ExtraLife, 1),
+                ],
+            },
+            EnemyKind::Scout => DropTable {
+                chance: 0.15,
+                weights: &[
+                    (PickupKind::Medal, 6),
+                    (PickupKind::Weapon(WeaponKind::Twin), 1),
+                    (PickupKind::Weapon(WeaponKind::Spread), 1),
+                    (PickupKind::Bomb, 1),
+                ],
+            },
+        }
+    }
+
+    // What waves of this kind fire unless they say otherwise
+    pub fn pattern(&self) -> Pattern {
+        match self {
+            EnemyKind::Fighter => Pattern::aimed(),
+            EnemyKind::Scout => Pattern::from_name("burst").unwrap(),
+        }
+    }
 }
 
 impl Enemy {
-    pub fn new(speed: f64, seed: u64) -> Enemy {
-        let mut rng = Rand::new_with_seed(seed);
-        let position_x = rng.next_i64_in_range(-WALK_AREA_MAX_X as i64, WALK_AREA_MAX_X as i64) as f64;
-        let behaviour = match position_x < 0.0 {
-            true => FlyL2R,
-            false => FlyR2L
-        };
+    pub fn new(kind: EnemyKind, position_x: f64, behaviour: Behaviour, pattern: Option<Pattern>, aim: Aim) -> Enemy {
+        let position = Vec2::new(position_x, WINDOW_HEIGHT as f64 + 50.0);
 
         Enemy {
-            health: 5,
-            speed,
-            position_x,
-            position_y: WINDOW_HEIGHT as f64 + 50.0,
+            kind,
+            health: kind.health(),
+            speed: kind.speed(),
+            position,
             behaviour,
-            shoot_timer: SHOOT_INTERVAL,
-            sprite_id: SpriteID(1),
-            rng,
+            motion: Motion::new(position),
+            target: Vec2::ZERO,
+            emitter: pattern.map(|pattern| Emitter::new(pattern, aim, SHOOT_INTERVAL)),
+            squadron: None,
+            leader: false,
+            formation_offset: None,
+            path_offset: Vec2::ZERO,
         }
     }
 
-    pub fn damage(&mut self) {
-        if self.health >= 1 {
-            self.health -= 1;
+    // Puts the enemy somewhere other than its spawn point, so tests don't have to fly it there
+    #[cfg(test)]
+    pub fn at(self, position: Vec2) -> Enemy {
+        Enemy {
+            position,
+            motion: Motion::new(position),
+            ..self
+        }
+    }
+
+    pub fn lead(self, squadron: u32) -> Enemy {
+        Enemy {
+            squadron: Some(squadron),
+            leader: true,
+            ..self
         }
     }
 
+    pub fn fly_wing(self, squadron: u32, offset: Vec2) -> Enemy {
+        Enemy {
+            squadron: Some(squadron),
+            formation_offset: Some(offset),
+            ..self
+        }
+    }
+
+    pub fn squadron(&self) -> Option<u32> {
+        self.squadron
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.leader
+    }
+
+    pub fn leads(&self, squadron: u32) -> bool {
+        self.leader && self.squadron == Some(squadron) && self.alive()
+    }
+
+    // Squadron of a wingman still holding formation
+    pub fn formation_squadron(&self) -> Option<u32> {
+        self.formation_offset.and(self.squadron)
+    }
+
+    pub fn hold_formation(&mut self, leader: Vec2) {
+        if let Some(offset) = self.formation_offset {
+            self.position = leader + offset;
+        }
+    }
+
+    // Leader was shot down, so fly solo and go for the player
+    pub fn break_formation(&mut self) {
+        self.formation_offset = None;
+        self.path_offset = Vec2::ZERO;
+        self.behaviour = Behaviour::from_name("dive").unwrap();
+        self.motion = Motion::new(self.position);
+    }
+
+    // Leader got away, so carry on along its path, keeping the same place in the formation
+    pub fn follow_path_of(&mut self, leader: &Enemy) {
+        if let Some(offset) = self.formation_offset.take() {
+            self.behaviour = leader.behaviour.clone();
+            self.motion = leader.motion.clone();
+            self.path_offset = offset;
+        }
+    }
+
+    pub fn kind(&self) -> EnemyKind {
+        self.kind
+    }
+
+    pub fn set_target(&mut self, target: Vec2) {
+        self.target = target;
+    }
+
+    // Spawn point is above the screen, so only enemies that flew back up count as gone there.
+    // Wingmen in formation go wherever their leader goes, even if they trail off screen for a while.
+    pub fn off_screen(&self) -> bool {
+        if self.formation_offset.is_some() {
+            return false;
+        }
+        self.position.y < -20.0
+            || self.position.y > WINDOW_HEIGHT as f64 + 60.0
+            || self.position.x > WALK_AREA_MAX_X + 20.0
+            || self.position.x < -WALK_AREA_MAX_X - 20.0
+    }
+
+    pub fn damage(&mut self, amount: u8) {
+        self.health = self.health.saturating_sub(amount);
+    }
+
     pub fn alive(&self) -> bool {
         self.health > 0
     }
 
-    pub fn can_shoot(&self) -> bool {
-        self.shoot_timer < 0.0
+    pub fn destroy(&mut self) {
+        self.health = 0;
     }
 
-    pub fn reset_shoot_interval(&mut self) {
-        self.shoot_timer = SHOOT_INTERVAL;
+    // Bullets fired this update. Only opens fire while well inside the screen.
+    pub fn fire(&mut self, step: f64, target: Target, rng: &mut Rng) -> Vec<Bullet> {
+        let can_fire = self.position.y < 250.0 && self.position.y > 30.0;
+        match &mut self.emitter {
+            Some(emitter) => emitter.update(step, self.position, target, can_fire, rng),
+            None => vec![],
+        }
     }
 }
 
 impl Entity for Enemy {
-    fn on_update(&mut self, _game_status: &mut GameStatus, renderer: &mut Renderer, sprites: &mut SpriteBank, _input: &Input, delta_time: f64, game_speed: f64) {
-        self.shoot_timer -= game_speed * delta_time;
-        self.position_y -= self.speed * game_speed * delta_time;
+    fn on_update(&mut self, _controls: &Controls, delta_time: f64, game_speed: f64) {
+        // Wingmen are moved by hold_formation instead
+        if self.formation_offset.is_some() {
+            return;
+        }
+
+        let on_path = self.motion.step(&self.behaviour, self.position - self.path_offset, self.target, self.speed, game_speed * delta_time);
+        self.position = on_path + self.path_offset;
+    }
 
+    fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    fn collider_radius(&self) -> i64 {
+        5
+    }
+
+    fn layer(&self) -> Option<Layer> {
+        Some(Layer::Enemy)
+    }
+
+    // Kamikazes are out to ram the player, everyone else only gets in the way of shots
+    fn mask(&self) -> Mask {
         match self.behaviour {
-            FlyL2R => {
-                self.position_x += self.speed / 2.0 * game_speed * delta_time;
-            }
-            FlyR2L => {
-                self.position_x -= self.speed / 2.0 * game_speed * delta_time;
-            }
-        };
+            Behaviour::Kamikaze { .. } => Mask::of(&[Layer::Player]),
+            _ => Layer::Enemy.default_mask(),
+        }
+    }
+}
 
-        let sprite = sprites.get_sprite(&self.sprite_id).unwrap();
+impl Drawable for Enemy {
+    fn on_draw(&self, renderer: &mut Renderer, sprites: &mut SpriteBank, rng: &mut Rng) {
+        let sprite = sprites.get_sprite(&SPRITE_ID).unwrap();
 
         let x_offset = (sprite.get_width() / 2) as i64;
         let y_offset = (sprite.get_height() / 2) as i64;
+        let (x, y) = self.position.rounded();
 
-        renderer.sprite(sprite, self.position_x as i64 - x_offset, self.position_y as i64 - y_offset, false);
+        renderer.sprite(sprite, x - x_offset, y - y_offset, false);
 
         renderer.line(
-            self.position_x as i64 - 2,
-            self.position_y as i64 + 8,
-            self.position_x as i64 - 2,
-            self.position_y as i64 + 8 +  self.rng.next_i64_in_range(0, 5),
+            x - 2,
+            y + 8,
+            x - 2,
+            y + 8 +  rng.next_i64_in_range(0, 5),
             FlaskColor::Yellow as u8
         );
         renderer.line(
-            self.position_x as i64 - 1,
-            self.position_y as i64 + 8,
-            self.position_x as i64 - 1,
-            self.position_y as i64 + 8 + self.rng.next_i64_in_range(2, 7),
+            x - 1,
+            y + 8,
+            x - 1,
+            y + 8 + rng.next_i64_in_range(2, 7),
             FlaskColor::White as u8
         );
         renderer.line(
-            self.position_x as i64,
-            self.position_y as i64 + 8,
-            self.position_x as i64,
-            self.position_y as i64 + 8 + self.rng.next_i64_in_range(0, 5),
+            x,
+            y + 8,
+            x,
+            y + 8 + rng.next_i64_in_range(0, 5),
             FlaskColor::Yellow as u8
         );
     }
-
-    fn x(&self) -> i64 {
-        self.position_x as i64
-    }
-
-    fn y(&self) -> i64 {
-        self.position_y as i64
-    }
-
-    fn collider_radius(&self) -> i64 {
-        5
-    }
 }
 
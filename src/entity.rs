@@ -17,32 +17,43 @@
 // along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
 //
 
-use flask::game_status::GameStatus;
-use flask::renderer::Renderer;
-use flask::input::Input;
-use flask::sprite::SpriteBank;
+use crate::collider::Collider;
+use crate::collision::{Layer, Mask};
+use crate::controls::Controls;
+use crate::vec2::Vec2;
 
 pub trait Entity {
-    fn on_update(&mut self, game_status: &mut GameStatus, renderer: &mut Renderer, sprites: &mut SpriteBank, input: &Input, delta_time: f64, game_speed : f64);
+    fn on_update(&mut self, controls: &Controls, delta_time: f64, game_speed : f64);
 
-    fn x(&self) -> i64;
-
-    fn y(&self) -> i64;
+    // Sub-pixel, rounded only when drawn
+    fn position(&self) -> Vec2;
 
     fn collider_radius(&self) -> i64;
 
-    fn distance(&self, other: &dyn Entity) -> f64 {
-        (((self.x() - other.x()).pow(2) + (self.y() - other.y()).pow(2)) as f64).sqrt()
+    // Shape to collide with, a circle of collider_radius unless an entity needs something else
+    fn collider(&self) -> Collider {
+        Collider::Circle { radius: self.collider_radius() as f64 }
     }
 
-    fn angle(&self, other: &dyn Entity) -> f64 {
-        let delta_x = (other.x() - self.x()) as f64;
-        let delta_y = (other.y() - self.y()) as f64;
-        let radians = delta_y.atan2(delta_x);
-        radians * 180.0 / std::f64::consts::PI
+    // How far it moved during the last update, for things fast enough to need swept collision
+    fn last_move(&self) -> Vec2 {
+        Vec2::ZERO
+    }
+
+    // Collision layer, None for things nothing collides with
+    fn layer(&self) -> Option<Layer> {
+        None
+    }
+
+    fn mask(&self) -> Mask {
+        self.layer().map_or(Mask::NONE, |layer| layer.default_mask())
+    }
+
+    fn distance(&self, other: &dyn Entity) -> f64 {
+        self.position().distance(other.position())
     }
 
     fn intersects(&self, other: &dyn Entity) -> bool {
-        (self.distance(other) - self.collider_radius() as f64 - other.collider_radius() as f64) < 0.0
+        self.collider().overlaps(self.position(), &other.collider(), other.position())
     }
 }
@@ -17,32 +17,30 @@
 // along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
 //
 
-use flask::game_status::GameStatus;
 use flask::renderer::Renderer;
-use flask::input::Input;
 use flask::palette::FlaskColor;
 use flask::sprite::SpriteBank;
-use flask::rand::Rand;
 
+use crate::controls::Controls;
+use crate::drawable::Drawable;
 use crate::entity::Entity;
+use crate::rng::Rng;
+use crate::vec2::Vec2;
 
 const EXPLOSION_TIME: f64 = 5.0;
 const MAX_OFFSET: i64 = 5;
 
+#[derive(Clone)]
 pub struct Explosion {
     time_remaining: f64,
-    position_x: f64,
-    position_y: f64,
-    rng: Rand,
+    position: Vec2,
 }
 
 impl Explosion {
-    pub fn new(position_x: i64, position_y: i64) -> Explosion {
+    pub fn new(position: Vec2) -> Explosion {
         Explosion {
             time_remaining: EXPLOSION_TIME,
-            position_x: position_x as f64,
-            position_y: position_y as f64,
-            rng: Rand::new_with_seed((position_x + position_y) as u64),
+            position,
         }
     }
 
@@ -52,30 +50,31 @@ impl Explosion {
 }
 
 impl Entity for Explosion {
-    fn on_update(&mut self, _game_status: &mut GameStatus, renderer: &mut Renderer, _sprites: &mut SpriteBank, _input: &Input, delta_time: f64, game_speed: f64) {
+    fn on_update(&mut self, _controls: &Controls, delta_time: f64, game_speed: f64) {
         self.time_remaining -= game_speed  * delta_time;
+    }
+
+    fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    fn collider_radius(&self) -> i64 {
+        0
+    }
+}
 
+impl Drawable for Explosion {
+    fn on_draw(&self, renderer: &mut Renderer, _sprites: &mut SpriteBank, rng: &mut Rng) {
+        let (x, y) = self.position.rounded();
         for _i in 0..10 {
-            let offset_x = self.rng.next_i64_in_range(-MAX_OFFSET, MAX_OFFSET);
-            let offset_y = self.rng.next_i64_in_range(-MAX_OFFSET, MAX_OFFSET);
-            let color = match self.rng.next_bool() {
+            let offset_x = rng.next_i64_in_range(-MAX_OFFSET, MAX_OFFSET);
+            let offset_y = rng.next_i64_in_range(-MAX_OFFSET, MAX_OFFSET);
+            let color = match rng.next_bool() {
                 true => FlaskColor::Red,
                 false => FlaskColor::Yellow
             };
 
-            renderer.circle_filled(self.position_x as i64 + offset_x, self.position_y as i64 + offset_y, 3, color as u8);
+            renderer.circle_filled(x + offset_x, y + offset_y, 3, color as u8);
         }
     }
-
-    fn x(&self) -> i64 {
-        self.position_x as i64
-    }
-
-    fn y(&self) -> i64 {
-        self.position_y as i64
-    }
-
-    fn collider_radius(&self) -> i64 {
-        0
-    }
 }
\ No newline at end of file
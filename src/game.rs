@@ -17,210 +17,255 @@
 // along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
 //
 
-use flask::rand::Rand;
 use flask::scene::Scene;
 use flask::renderer::Renderer;
 use flask::game_status::GameStatus;
-use flask::input::{Input, Key, State};
+use flask::input::Input;
 use flask::font::Font;
 use flask::palette::FlaskColor;
 use flask::sprite::{SpriteBank, Sprite};
 
-use crate::player::Player;
-use crate::entity::Entity;
-use crate::enemy::Enemy;
-use crate::projectile::Projectile;
+use crate::controls::Controls;
+use crate::drawable::Drawable;
 use crate::game_over::GameOver;
-use crate::explosion::Explosion;
-use crate::cloud::Cloud;
-use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
-
-pub const WALK_AREA_MAX_X: f64 = (WINDOW_WIDTH / 2) as f64;
-pub const WALK_AREA_MAX_Y: f64 = WINDOW_HEIGHT as f64;
-pub const SPAWN_INTERVAL: f64 = 10.0;
+use crate::overlay::OverlayStack;
+use crate::pause_menu::PauseMenu;
+use crate::recording::{self, Recording};
+use crate::rng::{stream_rng, Rng, Stream};
+use crate::settings;
+use crate::stage::StagePhase;
+use crate::world::{World, RunConfig};
+
+// How long the stage name stays on screen when a stage starts
+const STAGE_TITLE_TIME: f64 = 2.0;
+const BONUS_TIME: f64 = 1.5;
+const BOSS_BAR_WIDTH: i64 = 100;
+// Width of the red frame drawn while the player is flashing from a hit
+const FLASH_BORDER: i64 = 3;
 
 pub struct Game {
     font: Font,
-    score: u128,
-    game_speed: f64,
-    player: Player,
-    spawn_timer: f64,
-    score_timer: f64,
-    enemies: Vec<Enemy>,
-    projectiles: Vec<Projectile>,
-    player_projectiles: Vec<Projectile>,
-    explosions : Vec<Explosion>,
-    clouds: Vec<Cloud>,
+    world: World,
+    recording: Option<Recording>,
+    // World only advances while this is empty, which freezes every gameplay timer while paused
+    overlays: OverlayStack,
+    previous_controls: Controls,
+    // Enter also confirms menus, so it only drops a bomb once it has been let go after one
+    enter_released: bool,
     sprite_bank: SpriteBank,
-    rng: Rand,
+    particle_rng: Rng,
 }
 
 impl Game {
-    pub fn new() -> Game {
+    pub fn new(config: RunConfig) -> Game {
         Game {
             font: Font::load_3x5().unwrap(),
-            score: 0,
-            game_speed: 10.0,
-            player: Player::new(),
-            spawn_timer: SPAWN_INTERVAL * 2.0,
-            score_timer: 0.0,
-            enemies: vec![],
-            projectiles: vec![],
-            player_projectiles: vec![],
-            explosions: vec![],
-            clouds: vec![],
+            world: World::new(config),
+            recording: Some(Recording::new(config)),
+            overlays: OverlayStack::new(),
+            previous_controls: Controls::default(),
+            enter_released: false,
             sprite_bank: SpriteBank::new(),
-            rng: Rand::new()
+            particle_rng: stream_rng(config.seed, Stream::Particles)
         }
     }
-}
-
-impl Scene for Game {
-    fn on_start(&mut self, renderer: &mut Renderer) {
-        self.sprite_bank.add_sprite(Sprite::from_indexed_8bit_png(include_bytes!("../assets/player.png")).unwrap());
-        self.sprite_bank.add_sprite(Sprite::from_indexed_8bit_png(include_bytes!("../assets/enemy.png")).unwrap());
 
-        for _ in 0..30 {
-            self.clouds.push(Cloud::new(&mut self.rng));
+    // Game that plays back already recorded input, so it doesn't record again
+    pub fn playback(config: RunConfig) -> Game {
+        Game {
+            recording: None,
+            ..Game::new(config)
         }
+    }
 
-        renderer.set_background_color(FlaskColor::Teal as u8).unwrap();
-        renderer.set_camera_y(renderer.get_window_size().1 / 2);
+    pub fn world(&self) -> &World {
+        &self.world
     }
 
-    fn on_update(&mut self, game_status: &mut GameStatus, renderer: &mut Renderer, input: &Input, delta_time: f64) -> Option<Box<dyn Scene>> {
-        renderer.clear_screen();
+    pub fn restore(&mut self, world: World) {
+        self.world = world;
+    }
 
-        if input.get_key_state(Key::Escape) == State::Down {
-            game_status.quit();
-            return None;
+    // Advances the run by one frame of input, recording it so the run can be replayed later
+    pub fn step(&mut self, controls: &Controls, delta_time: f64) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(delta_time, *controls);
         }
+        self.world.advance(controls, delta_time);
+    }
 
-        self.score_timer += delta_time * self.game_speed;
-        if self.score_timer >= 1.0 {
-            self.score += 1;
-            self.score_timer -= 1.0;
-        }
+    pub fn load(&mut self, renderer: &mut Renderer) {
+        self.sprite_bank.add_sprite(Sprite::from_indexed_8bit_png(include_bytes!("../assets/player.png")).unwrap());
+        self.sprite_bank.add_sprite(Sprite::from_indexed_8bit_png(include_bytes!("../assets/enemy.png")).unwrap());
 
-        self.spawn_timer -= delta_time * self.game_speed;
-        if self.spawn_timer < 0.0 {
-            self.spawn_timer = SPAWN_INTERVAL;
-            self.enemies.push(Enemy::new(5.0, self.rng.next_u64()))
-        }
+        renderer.set_background_color(FlaskColor::Teal as u8).unwrap();
+        renderer.set_camera_y(renderer.get_window_size().1 / 2);
+    }
 
+    fn draw_boss_bar(&self, renderer: &mut Renderer, fraction: f64, y: i64) {
+        let left = -BOSS_BAR_WIDTH / 2;
+        let right = left + BOSS_BAR_WIDTH;
+        let filled = left + (BOSS_BAR_WIDTH as f64 * fraction) as i64;
 
-        for cloud in &mut self.clouds {
-            cloud.on_update(game_status, renderer, &mut self.sprite_bank, input, delta_time, self.game_speed);
+        for row in 0..3 {
+            renderer.line(left, y - row, right, y - row, FlaskColor::White as u8);
+            if filled > left {
+                renderer.line(left, y - row, filled, y - row, FlaskColor::Red as u8);
+            }
         }
+    }
 
-        let mut enemies_to_remove = vec![];
-        for i in 0..self.enemies.len() {
-            let enemy = &mut self.enemies[i];
-            enemy.on_update(game_status, renderer, &mut self.sprite_bank, input, delta_time, self.game_speed);
+    pub fn draw(&mut self, renderer: &mut Renderer) {
+        let world = &self.world;
+        let sprites = &mut self.sprite_bank;
+        let rng = &mut self.particle_rng;
+        let (window_w, window_h) = renderer.get_window_size();
 
-            if enemy.can_shoot() {
-                let angle = enemy.angle(&self.player);
-                if enemy.y() < 250 && enemy.y() > 30 {
-                    self.projectiles.push(Projectile::new(10.0, angle, enemy.x(), enemy.y(), FlaskColor::Red as u8));
-                    enemy.reset_shoot_interval();
-                }
-            }
+        // Screen shake throws the scene, not the HUD
+        let shake = world.shake();
+        let shake_y = match shake > 0 {
+            true => rng.next_i64_in_range(-shake, shake + 1),
+            false => 0,
+        };
+        renderer.set_camera_y(window_h / 2 + shake_y);
 
-            if enemy.y() < -20 || enemy.x() > WALK_AREA_MAX_X as i64 + 20 || enemy.x() < -WALK_AREA_MAX_X as i64 - 20 {
-                enemies_to_remove.push(i);
-            }
+        for cloud in world.clouds() {
+            cloud.on_draw(renderer, sprites, rng);
+        }
+        for enemy in world.enemies() {
+            enemy.on_draw(renderer, sprites, rng);
+        }
+        if let Some(boss) = world.boss() {
+            boss.on_draw(renderer, sprites, rng);
+        }
+        for pickup in world.pickups() {
+            pickup.on_draw(renderer, sprites, rng);
+        }
+        for projectile in world.projectiles() {
+            projectile.on_draw(renderer, sprites, rng);
+        }
+        world.player().on_draw(renderer, sprites, rng);
+        for projectile in world.player_projectiles() {
+            projectile.on_draw(renderer, sprites, rng);
+        }
+        for explosion in world.explosions() {
+            explosion.on_draw(renderer, sprites, rng);
+        }
+        for blast in world.blasts() {
+            blast.on_draw(renderer, sprites, rng);
         }
 
-        let mut projectiles_to_remove = vec![];
-        for i in 0..self.projectiles.len() {
-            let projectile = &mut self.projectiles[i];
-            projectile.on_update(game_status, renderer, &mut self.sprite_bank, input, delta_time, self.game_speed);
-
-            if projectile.intersects(&self.player) {
-                self.player.damage();
-                projectiles_to_remove.push(i);
-            }
+        renderer.set_camera_y(window_h / 2);
 
-            if projectile.y() < -20 || projectile.distance(&self.player) > 300.0 {
-                projectiles_to_remove.push(i);
+        if world.flashing() {
+            for row in 0..FLASH_BORDER {
+                renderer.line(-(window_w / 2), row, window_w / 2, row, FlaskColor::Red as u8);
+                renderer.line(-(window_w / 2), window_h - 1 - row, window_w / 2, window_h - 1 - row, FlaskColor::Red as u8);
+                renderer.line(-(window_w / 2) + row, 0, -(window_w / 2) + row, window_h, FlaskColor::Red as u8);
+                renderer.line(window_w / 2 - 1 - row, 0, window_w / 2 - 1 - row, window_h, FlaskColor::Red as u8);
             }
         }
 
-        if !self.player.alive() {
-            return Some(Box::new(GameOver::new(self.score)));
-        }
+        let score_text = String::from(format!("SCORE: {}", world.score()));
+        renderer.text(&score_text, &self.font, -(window_w / 2) + 5, window_h - 10, FlaskColor::Purple as u8);
 
-        self.player.on_update(game_status, renderer, &mut self.sprite_bank, input, delta_time, self.game_speed);
+        let lives_text = String::from(format!("LIVES: {}", world.player().health()));
+        renderer.text(&lives_text, &self.font, -(window_w / 2) + 5, window_h - 20, FlaskColor::Purple as u8);
 
-        if self.player.can_shoot() {
-            self.player_projectiles.push(Projectile::new(30.0, 90.0, self.player.x() - 10, self.player.y(), FlaskColor::Yellow as u8));
-            self.player_projectiles.push(Projectile::new(30.0, 90.0, self.player.x() + 10, self.player.y(), FlaskColor::Yellow as u8));
-            self.player.reset_shoot_interval();
-        }
+        let bombs_text = String::from(format!("BOMBS: {}", world.player().bombs()));
+        renderer.text(&bombs_text, &self.font, -(window_w / 2) + 45, window_h - 20, FlaskColor::Purple as u8);
 
-        let mut player_projectiles_to_remove = vec![];
-        for i in 0..self.player_projectiles.len() {
-            let projectile = &mut self.player_projectiles[i];
-            projectile.on_update(game_status, renderer, &mut self.sprite_bank, input, delta_time, self.game_speed);
+        let weapon = world.player().weapon();
+        let weapon_text = String::from(format!("{} {}", weapon.kind().name(), weapon.level()));
+        renderer.text(&weapon_text, &self.font, -(window_w / 2) + 5, window_h - 30, FlaskColor::Purple as u8);
 
-            if projectile.distance(&self.player) > 400.0 {
-                player_projectiles_to_remove.push(i);
-                continue;
-            }
+        let stage_runner = world.stage_runner();
+        if stage_runner.stage_time() < STAGE_TITLE_TIME {
+            let stage_text = stage_runner.stage().name.clone();
+            renderer.text(&stage_text, &self.font, -(4 * (stage_text.len() as i64 / 2)), window_h / 2, FlaskColor::Purple as u8);
+        }
 
-            for y in 0..self.enemies.len() {
-                let enemy = &mut self.enemies[y];
-                if projectile.intersects(enemy) {
-                    enemy.damage();
-                    player_projectiles_to_remove.push(i);
-                    if !enemy.alive() {
-                        self.score += 100;
-                        enemies_to_remove.push(y);
-                        self.explosions.push(Explosion::new(enemy.x(), enemy.y()));
-                        break;
-                    }
+        let phase_time = stage_runner.phase_time();
+        match stage_runner.phase() {
+            StagePhase::BossWarning => {
+                // Blinks a few times a second
+                if (phase_time * 4.0) as i64 % 2 == 0 {
+                    let warning_text = String::from("WARNING");
+                    renderer.text(&warning_text, &self.font, -(4 * (warning_text.len() as i64 / 2)), window_h / 2 + 10, FlaskColor::Red as u8);
+                    let boss_text = String::from("BOSS APPROACHING");
+                    renderer.text(&boss_text, &self.font, -(4 * (boss_text.len() as i64 / 2)), window_h / 2, FlaskColor::Red as u8);
                 }
             }
+            StagePhase::Clear => {
+                let clear_text = String::from("STAGE CLEAR");
+                renderer.text(&clear_text, &self.font, -(4 * (clear_text.len() as i64 / 2)), window_h / 2, FlaskColor::Purple as u8);
+            }
+            _ => {}
         }
 
-        let mut explosions_to_remove = vec![];
-        for i in 0..self.explosions.len() {
-            let explosion = &mut self.explosions[i];
-            explosion.on_update(game_status, renderer, &mut self.sprite_bank, input, delta_time, self.game_speed);
+        if let Some(boss) = world.boss() {
+            if !boss.is_defeated() {
+                self.draw_boss_bar(renderer, boss.health_fraction(), window_h - 6);
+            }
+        }
 
-            if !explosion.alive() {
-                explosions_to_remove.push(i);
+        if let Some((label, bonus, time)) = world.bonus() {
+            if time < BONUS_TIME {
+                let bonus_text = String::from(format!("{} {}", label, bonus));
+                renderer.text(&bonus_text, &self.font, -(4 * (bonus_text.len() as i64 / 2)), window_h / 2 - 10, FlaskColor::Yellow as u8);
             }
         }
 
-        let (window_w, window_h) = renderer.get_window_size();
+        if settings::get().show_seed {
+            let seed_text = String::from(format!("SEED: {}", world.config().seed));
+            renderer.text(&seed_text, &self.font, -(window_w / 2) + 5, 5, FlaskColor::Purple as u8);
+        }
+    }
+}
 
-        let score_text = String::from(format!("SCORE: {}", self.score));
-        renderer.text(&score_text, &self.font, -(window_w / 2) + 5, window_h - 10, FlaskColor::Purple as u8);
+impl Scene for Game {
+    fn on_start(&mut self, renderer: &mut Renderer) {
+        self.load(renderer);
+    }
 
-        let lives_text = String::from(format!("LIVES: {}", self.player.health()));
-        renderer.text(&lives_text, &self.font, -(window_w / 2) + 5, window_h - 20, FlaskColor::Purple as u8);
+    fn on_update(&mut self, _game_status: &mut GameStatus, renderer: &mut Renderer, input: &Input, delta_time: f64) -> Option<Box<dyn Scene>> {
+        renderer.clear_screen();
 
-        // Remove things
-        enemies_to_remove.sort();
-        enemies_to_remove.dedup();
-        for i in enemies_to_remove.iter().rev() {
-            self.enemies.remove(*i);
-        }
-        projectiles_to_remove.sort();
-        for i in projectiles_to_remove.iter().rev() {
-            self.projectiles.remove(*i);
+        let mut controls = Controls::from_input(input);
+        let pressed = controls.pressed(&self.previous_controls);
+        self.previous_controls = controls;
+
+        if !controls.enter {
+            self.enter_released = true;
         }
-        player_projectiles_to_remove.sort();
-        for i in player_projectiles_to_remove.iter().rev() {
-            self.player_projectiles.remove(*i);
+        if !self.enter_released {
+            controls.enter = false;
         }
-        explosions_to_remove.sort();
-        for i in explosions_to_remove.iter().rev() {
-            self.explosions.remove(*i);
+
+        let mut next_scene = None;
+        if self.overlays.is_empty() {
+            if pressed.escape {
+                self.overlays.push(Box::new(PauseMenu::new(self.world.config())));
+            } else {
+                self.step(&controls, delta_time);
+            }
+
+            if self.world.is_over() {
+                if let Some(recording) = &mut self.recording {
+                    recording.finish(self.world.score());
+                    recording::store_last(recording);
+                }
+                return Some(Box::new(GameOver::new(self.world.score(), self.world.config(), self.world.elapsed())));
+            }
+        } else {
+            next_scene = self.overlays.on_update(&pressed);
+            self.enter_released = false;
         }
 
-        None
+        self.draw(renderer);
+        self.overlays.on_draw(renderer, &self.font);
+
+        next_scene
     }
 
     fn on_destroy(&mut self) {
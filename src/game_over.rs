@@ -20,24 +20,62 @@
 use flask::scene::Scene;
 use flask::renderer::Renderer;
 use flask::game_status::GameStatus;
-use flask::input::{Input, State, Key};
+use flask::input::Input;
 use flask::font::Font;
 use flask::palette::FlaskColor;
 
+use crate::controls::Controls;
 use crate::game::Game;
+use crate::high_scores::{Entry, HighScoreTable, InitialsEntry, INITIALS_LENGTH};
+use crate::storage;
+use crate::title::Title;
+use crate::world::RunConfig;
 
 pub struct GameOver {
     score: u128,
+    config: RunConfig,
+    duration: f64,
+    // Present while the player is typing initials for a new high score
+    initials_entry: Option<InitialsEntry>,
+    rank: Option<usize>,
     font: Font,
+    previous_controls: Controls,
 }
 
 impl GameOver {
-    pub fn new(score: u128) -> GameOver {
+    pub fn new(score: u128, config: RunConfig, duration: f64) -> GameOver {
+        let table = HighScoreTable::load(storage::default_storage().as_ref());
+        let initials_entry = match table.qualifies(score) {
+            true => Some(InitialsEntry::new()),
+            false => None,
+        };
+
         GameOver {
             score,
-            font: Font::load_3x5().unwrap()
+            config,
+            duration,
+            initials_entry,
+            rank: None,
+            font: Font::load_3x5().unwrap(),
+            // Keys still held from the game shouldn't count as presses
+            previous_controls: Controls::from_bits(u8::MAX),
         }
     }
+
+    fn submit(&mut self, initials: [u8; INITIALS_LENGTH]) {
+        let mut storage = storage::default_storage();
+        let mut table = HighScoreTable::load(storage.as_ref());
+
+        self.rank = table.insert(Entry {
+            initials,
+            score: self.score,
+            seed: self.config.seed,
+            mode: self.config.mode,
+            duration: self.duration as u32,
+            date: storage::unix_time(),
+        });
+        table.save(storage.as_mut());
+    }
 }
 
 impl Scene for GameOver {
@@ -48,6 +86,10 @@ impl Scene for GameOver {
     fn on_update(&mut self, game_status: &mut GameStatus, renderer: &mut Renderer, input: &Input, _delta_time: f64) -> Option<Box<dyn Scene>> {
         renderer.clear_screen();
 
+        let controls = Controls::from_input(input);
+        let pressed = controls.pressed(&self.previous_controls);
+        self.previous_controls = controls;
+
         let game_over = String::from("GAME OVER");
         let score_text = String::from("SCORE:");
         let score = String::from(format!("{}", self.score));
@@ -55,11 +97,40 @@ impl Scene for GameOver {
         renderer.text(&score_text, &self.font, -(4 * 3), -10, FlaskColor::Red as u8);
         renderer.text(&score, &self.font, -(4 * (score.len() as i64 / 2)) , -20, FlaskColor::Yellow as u8);
 
-        if input.get_key_state(Key::Enter) == State::Down {
-            return Some(Box::new(Game::new()))
+        let seed_text = String::from(format!("SEED: {}", self.config.seed));
+        renderer.text(&seed_text, &self.font, -(4 * (seed_text.len() as i64 / 2)), -40, FlaskColor::Red as u8);
+
+        if let Some(initials_entry) = &mut self.initials_entry {
+            let high_score_text = String::from("NEW HIGH SCORE - ENTER INITIALS");
+            renderer.text(&high_score_text, &self.font, -(4 * (high_score_text.len() as i64 / 2)), -60, FlaskColor::Red as u8);
+            initials_entry.on_draw(renderer, &self.font, -10, -70);
+
+            if let Some(initials) = initials_entry.on_update(&pressed) {
+                self.initials_entry = None;
+                self.submit(initials);
+            }
+            return None;
         }
 
-        if input.get_key_state(Key::Escape) == State::Down {
+        if let Some(rank) = self.rank {
+            let rank_text = String::from(format!("RANK {}", rank + 1));
+            renderer.text(&rank_text, &self.font, -(4 * (rank_text.len() as i64 / 2)), -30, FlaskColor::Red as u8);
+        }
+
+        let retry_text = String::from("D: SAME SEED");
+        renderer.text(&retry_text, &self.font, -(4 * (retry_text.len() as i64 / 2)), -50, FlaskColor::Red as u8);
+        let menu_text = String::from("ENTER: MENU");
+        renderer.text(&menu_text, &self.font, -(4 * (menu_text.len() as i64 / 2)), -60, FlaskColor::Red as u8);
+
+        if pressed.enter {
+            return Some(Box::new(Title::new()))
+        }
+
+        if pressed.right {
+            return Some(Box::new(Game::new(self.config)))
+        }
+
+        if pressed.escape {
             game_status.quit();
         }
 
@@ -69,4 +140,4 @@ impl Scene for GameOver {
     fn on_destroy(&mut self) {
 
     }
-}
\ No newline at end of file
+}
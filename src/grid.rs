@@ -0,0 +1,82 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use std::collections::HashMap;
+
+// About the size of the biggest regular enemy, so most things land in one to four cells
+pub const CELL_SIZE: i64 = 32;
+
+// Uniform grid broadphase. Colliders are registered by index every update and queries hand back
+// the indices of everything sharing a cell, which still need an exact overlap check.
+#[derive(Clone)]
+pub struct SpatialGrid {
+    cell_size: i64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: i64) -> SpatialGrid {
+        SpatialGrid {
+            cell_size: cell_size.max(1),
+            cells: HashMap::new(),
+        }
+    }
+
+    // Empties the cells but keeps their allocations for the next update
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    // Registers an index in every cell overlapping the square around a point
+    pub fn insert_area(&mut self, index: usize, x: i64, y: i64, radius: i64) {
+        let (min, max) = self.cell_range(x, y, radius);
+        for cell_x in min.0..=max.0 {
+            for cell_y in min.1..=max.1 {
+                self.cells.entry((cell_x, cell_y)).or_insert_with(Vec::new).push(index);
+            }
+        }
+    }
+
+    // Everything registered in cells overlapping the square around a point, in ascending index order
+    pub fn query_area(&self, x: i64, y: i64, radius: i64) -> Vec<usize> {
+        let (min, max) = self.cell_range(x, y, radius);
+        let mut found = vec![];
+        for cell_x in min.0..=max.0 {
+            for cell_y in min.1..=max.1 {
+                if let Some(cell) = self.cells.get(&(cell_x, cell_y)) {
+                    found.extend_from_slice(cell);
+                }
+            }
+        }
+        // Sorted so results don't depend on hash map order, keeping runs deterministic
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    fn cell_range(&self, x: i64, y: i64, radius: i64) -> ((i64, i64), (i64, i64)) {
+        let radius = radius.max(0);
+        (
+            ((x - radius).div_euclid(self.cell_size), (y - radius).div_euclid(self.cell_size)),
+            ((x + radius).div_euclid(self.cell_size), (y + radius).div_euclid(self.cell_size)),
+        )
+    }
+}
@@ -0,0 +1,317 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::renderer::Renderer;
+use flask::font::Font;
+use flask::palette::FlaskColor;
+
+use crate::controls::Controls;
+use crate::menu;
+use crate::mode::Mode;
+use crate::overlay::{Action, Overlay};
+use crate::storage::{self, Storage};
+
+const STORAGE_KEY: &str = "sky_combat_high_scores";
+const TABLE_SIZE: usize = 10;
+pub const INITIALS_LENGTH: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub initials: [u8; INITIALS_LENGTH],
+    pub score: u128,
+    pub seed: u64,
+    pub mode: Mode,
+    // Run length in whole seconds
+    pub duration: u32,
+    // Unix time the run ended
+    pub date: u64,
+}
+
+impl Entry {
+    // One line per entry: initials score seed mode duration date
+    fn to_line(&self) -> String {
+        format!(
+            "{} {} {} {} {} {}",
+            String::from_utf8_lossy(&self.initials),
+            self.score,
+            self.seed,
+            self.mode.to_id(),
+            self.duration,
+            self.date
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Entry> {
+        let mut fields = line.split_whitespace();
+
+        let initials: [u8; INITIALS_LENGTH] = fields.next()?.as_bytes().try_into().ok()?;
+        if !initials.iter().all(|letter| letter.is_ascii_uppercase()) {
+            return None;
+        }
+
+        Some(Entry {
+            initials,
+            score: fields.next()?.parse().ok()?,
+            seed: fields.next()?.parse().ok()?,
+            mode: Mode::from_id(fields.next()?.parse().ok()?)?,
+            duration: fields.next()?.parse().ok()?,
+            date: fields.next()?.parse().ok()?,
+        })
+    }
+}
+
+// Top scores, best first
+pub struct HighScoreTable {
+    entries: Vec<Entry>,
+}
+
+impl HighScoreTable {
+    // Lines that don't parse are dropped rather than failing the whole table
+    pub fn load(storage: &dyn Storage) -> HighScoreTable {
+        let mut table = HighScoreTable {
+            entries: vec![],
+        };
+
+        if let Some(text) = storage.load(STORAGE_KEY) {
+            for entry in text.lines().filter_map(Entry::from_line) {
+                table.insert(entry);
+            }
+        }
+
+        table
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        let lines: Vec<String> = self.entries.iter().map(|entry| entry.to_line()).collect();
+        storage.save(STORAGE_KEY, &lines.join("\n"));
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn qualifies(&self, score: u128) -> bool {
+        if score == 0 {
+            return false;
+        }
+        self.entries.len() < TABLE_SIZE || self.entries.last().map_or(true, |last| score > last.score)
+    }
+
+    // Returns rank the entry got, or None if it didn't make the table
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        let position = self.entries.iter().position(|other| other.score < entry.score).unwrap_or(self.entries.len());
+        if position >= TABLE_SIZE {
+            return None;
+        }
+
+        self.entries.insert(position, entry);
+        self.entries.truncate(TABLE_SIZE);
+        Some(position)
+    }
+}
+
+// Arcade style three letter name picker. W/S change the letter, A/D move between letters.
+pub struct InitialsEntry {
+    initials: [u8; INITIALS_LENGTH],
+    cursor: usize,
+}
+
+impl InitialsEntry {
+    pub fn new() -> InitialsEntry {
+        InitialsEntry {
+            initials: [b'A'; INITIALS_LENGTH],
+            cursor: 0,
+        }
+    }
+
+    // Returns chosen initials once Enter is pressed
+    pub fn on_update(&mut self, pressed: &Controls) -> Option<[u8; INITIALS_LENGTH]> {
+        let letter = &mut self.initials[self.cursor];
+        if pressed.up {
+            *letter = if *letter == b'Z' { b'A' } else { *letter + 1 };
+        }
+        if pressed.down {
+            *letter = if *letter == b'A' { b'Z' } else { *letter - 1 };
+        }
+        if pressed.left && self.cursor > 0 {
+            self.cursor -= 1;
+        }
+        if pressed.right && self.cursor + 1 < INITIALS_LENGTH {
+            self.cursor += 1;
+        }
+
+        match pressed.enter {
+            true => Some(self.initials),
+            false => None,
+        }
+    }
+
+    pub fn on_draw(&self, renderer: &mut Renderer, font: &Font, x: i64, y: i64) {
+        for (i, letter) in self.initials.iter().enumerate() {
+            let letter_x = x + 8 * i as i64;
+            let text = String::from(*letter as char);
+            let color = match i == self.cursor {
+                true => FlaskColor::Red,
+                false => FlaskColor::Purple,
+            };
+            renderer.text(&text, font, letter_x, y, color as u8);
+            if i == self.cursor {
+                renderer.line(letter_x, y - 2, letter_x + 2, y - 2, FlaskColor::Red as u8);
+            }
+        }
+    }
+}
+
+pub struct HighScoresView {
+    table: HighScoreTable,
+}
+
+impl HighScoresView {
+    pub fn new() -> HighScoresView {
+        HighScoresView {
+            table: HighScoreTable::load(storage::default_storage().as_ref()),
+        }
+    }
+}
+
+impl Overlay for HighScoresView {
+    fn on_update(&mut self, pressed: &Controls) -> Action {
+        match pressed.escape || pressed.enter {
+            true => Action::Pop,
+            false => Action::Stay,
+        }
+    }
+
+    fn on_draw(&self, renderer: &mut Renderer, font: &Font) {
+        let (_, window_h) = renderer.get_window_size();
+        let top = window_h / 2 + 70;
+
+        menu::panel(renderer, -100, top - 140, 200, 140);
+
+        let title = String::from("HIGH SCORES");
+        renderer.text(&title, font, -(4 * (title.len() as i64 / 2)), top - 10, FlaskColor::Red as u8);
+
+        if self.table.entries().is_empty() {
+            let empty_text = String::from("NO SCORES YET");
+            renderer.text(&empty_text, font, -(4 * (empty_text.len() as i64 / 2)), top - 30, FlaskColor::Purple as u8);
+        }
+
+        for (i, entry) in self.table.entries().iter().enumerate() {
+            let y = top - 25 - 10 * i as i64;
+            let line = String::from(format!(
+                "{:>2} {} {:>7} {:<8} {:>4}S {}",
+                i + 1,
+                String::from_utf8_lossy(&entry.initials),
+                entry.score,
+                entry.mode.name(),
+                entry.duration,
+                format_date(entry.date)
+            ));
+            renderer.text(&line, font, -95, y, FlaskColor::Purple as u8);
+        }
+    }
+}
+
+// YYYY-MM-DD from unix time
+fn format_date(unix_time: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let days = (unix_time / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn entry(initials: &[u8; INITIALS_LENGTH], score: u128) -> Entry {
+        Entry {
+            initials: *initials,
+            score,
+            seed: score as u64,
+            mode: Mode::Normal,
+            duration: 60,
+            date: 1_650_000_000,
+        }
+    }
+
+    fn scores(table: &HighScoreTable) -> Vec<u128> {
+        table.entries().iter().map(|entry| entry.score).collect()
+    }
+
+    #[test]
+    fn entries_are_kept_best_first() {
+        let mut table = HighScoreTable::load(&MemoryStorage::default());
+        assert_eq!(table.insert(entry(b"AAA", 300)), Some(0));
+        assert_eq!(table.insert(entry(b"BBB", 500)), Some(0));
+        assert_eq!(table.insert(entry(b"CCC", 400)), Some(1));
+        // Ties go below the entry already there
+        assert_eq!(table.insert(entry(b"DDD", 400)), Some(2));
+
+        assert_eq!(scores(&table), vec![500, 400, 400, 300]);
+        assert_eq!(table.entries()[1].initials, *b"CCC");
+    }
+
+    #[test]
+    fn table_is_truncated_to_its_size() {
+        let mut table = HighScoreTable::load(&MemoryStorage::default());
+        for i in 1..=TABLE_SIZE as u128 {
+            table.insert(entry(b"AAA", i * 100));
+        }
+        assert!(!table.qualifies(100));
+        assert!(table.qualifies(150));
+
+        assert_eq!(table.insert(entry(b"LOW", 50)), None);
+        assert_eq!(table.insert(entry(b"TOP", 5000)), Some(0));
+        assert_eq!(table.entries().len(), TABLE_SIZE);
+        assert_eq!(table.entries().last().unwrap().score, 200);
+    }
+
+    #[test]
+    fn table_survives_save_and_load() {
+        let mut storage = MemoryStorage::default();
+        let mut table = HighScoreTable::load(&storage);
+        table.insert(entry(b"ABC", 1200));
+        table.insert(entry(b"XYZ", 800));
+        table.save(&mut storage);
+
+        let loaded = HighScoreTable::load(&storage);
+        assert_eq!(scores(&loaded), vec![1200, 800]);
+        assert_eq!(loaded.entries()[0].initials, *b"ABC");
+        assert_eq!(loaded.entries()[1].seed, 800);
+    }
+
+    #[test]
+    fn broken_lines_are_skipped() {
+        let mut storage = MemoryStorage::default();
+        storage.save(STORAGE_KEY, "ABC 100 1 0 60 0\nnot a score\nab1 50 1 0 60 0");
+
+        let table = HighScoreTable::load(&storage);
+        assert_eq!(scores(&table), vec![100]);
+    }
+}
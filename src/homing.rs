@@ -0,0 +1,105 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use crate::vec2::Vec2;
+
+// Guidance for missiles. Headings are direction vectors, turn rates and cones are in degrees
+// and times in game time units.
+
+// A target that moved less than this since last update is taken to be the same one
+const TRACK_DISTANCE: f64 = 20.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Homing {
+    // Degrees per time unit it can turn
+    turn_rate: f64,
+    // Only locks onto targets within this many degrees either side of where it's heading
+    cone: f64,
+    // Steers while there is fuel left, then flies straight
+    fuel: f64,
+    // Goes off once this runs out
+    lifetime: f64,
+    target: Option<Vec2>,
+}
+
+impl Homing {
+    pub fn new(turn_rate: f64, cone: f64, fuel: f64, lifetime: f64) -> Homing {
+        Homing {
+            turn_rate,
+            cone,
+            fuel,
+            lifetime,
+            target: None,
+        }
+    }
+
+    // Quick and agile, for the player's missiles
+    pub fn player() -> Homing {
+        Homing::new(12.0, 60.0, 30.0, 50.0)
+    }
+
+    // Lazy turner that can be outflown
+    pub fn enemy() -> Homing {
+        Homing::new(4.0, 90.0, 25.0, 60.0)
+    }
+
+    pub fn has_fuel(&self) -> bool {
+        self.fuel > 0.0
+    }
+
+    pub fn expired(&self) -> bool {
+        self.lifetime <= 0.0
+    }
+
+    // Sticks with the current target while it's still around, otherwise locks onto the nearest one ahead
+    pub fn track(&mut self, position: Vec2, heading: Vec2, candidates: &[Vec2]) {
+        if let Some(target) = self.target {
+            let same = candidates.iter()
+                .copied()
+                .filter(|candidate| candidate.distance(target) < TRACK_DISTANCE)
+                .min_by(|a, b| a.distance(target).total_cmp(&b.distance(target)));
+            if same.is_some() {
+                self.target = same;
+                return;
+            }
+        }
+
+        self.target = candidates.iter()
+            .copied()
+            .filter(|candidate| heading.angle_to(*candidate - position).abs() <= self.cone)
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+    }
+
+    // Burns fuel and lifetime for `step` and returns the new heading
+    pub fn steer(&mut self, position: Vec2, heading: Vec2, step: f64) -> Vec2 {
+        self.lifetime -= step;
+        if !self.has_fuel() {
+            return heading;
+        }
+        self.fuel -= step;
+
+        match self.target {
+            Some(target) => {
+                let max_turn = self.turn_rate * step;
+                heading.rotate(heading.angle_to(target - position).clamp(-max_turn, max_turn))
+            }
+            None => heading,
+        }
+    }
+}
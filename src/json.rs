@@ -0,0 +1,376 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use std::fmt;
+
+// Minimal JSON reader for game data files. Every value remembers the line it started on,
+// so loaders can report problems where the designer made them.
+#[derive(Clone, Debug)]
+pub struct Value {
+    pub line: usize,
+    pub kind: Kind,
+}
+
+#[derive(Clone, Debug)]
+pub enum Kind {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(line: usize, message: impl Into<String>) -> Error {
+        Error {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self.kind {
+            Kind::Null => "null",
+            Kind::Bool(_) => "boolean",
+            Kind::Number(_) => "number",
+            Kind::String(_) => "string",
+            Kind::Array(_) => "array",
+            Kind::Object(_) => "object",
+        }
+    }
+
+    fn expected(&self, what: &str) -> Error {
+        Error::new(self.line, format!("expected {}, found {}", what, self.type_name()))
+    }
+
+    pub fn as_f64(&self) -> Result<f64, Error> {
+        match self.kind {
+            Kind::Number(number) => Ok(number),
+            _ => Err(self.expected("number")),
+        }
+    }
+
+    pub fn as_u32(&self) -> Result<u32, Error> {
+        let number = self.as_f64()?;
+        if number < 0.0 || number.fract() != 0.0 || number > u32::MAX as f64 {
+            return Err(Error::new(self.line, format!("expected whole non-negative number, found {}", number)));
+        }
+        Ok(number as u32)
+    }
+
+    pub fn as_bool(&self) -> Result<bool, Error> {
+        match self.kind {
+            Kind::Bool(value) => Ok(value),
+            _ => Err(self.expected("boolean")),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, Error> {
+        match &self.kind {
+            Kind::String(string) => Ok(string),
+            _ => Err(self.expected("string")),
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[Value], Error> {
+        match &self.kind {
+            Kind::Array(values) => Ok(values),
+            _ => Err(self.expected("array")),
+        }
+    }
+
+    pub fn as_object(&self) -> Result<&[(String, Value)], Error> {
+        match &self.kind {
+            Kind::Object(fields) => Ok(fields),
+            _ => Err(self.expected("object")),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<&Value>, Error> {
+        Ok(self.as_object()?.iter().find(|(name, _)| name == key).map(|(_, value)| value))
+    }
+
+    pub fn field(&self, key: &str) -> Result<&Value, Error> {
+        self.get(key)?.ok_or_else(|| Error::new(self.line, format!("missing field \"{}\"", key)))
+    }
+
+    // Rejects fields the loader doesn't know about, which are usually typos
+    pub fn check_fields(&self, known: &[&str]) -> Result<(), Error> {
+        for (name, value) in self.as_object()? {
+            if !known.contains(&name.as_str()) {
+                return Err(Error::new(value.line, format!("unknown field \"{}\", expected one of: {}", name, known.join(", "))));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn parse(text: &str) -> Result<Value, Error> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+        line: 1,
+    };
+
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position < parser.chars.len() {
+        return Err(Error::new(parser.line, "unexpected text after the end of the document"));
+    }
+
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(Error::new(self.line, format!("expected '{}', found '{}'", expected, c))),
+            None => Err(Error::new(self.line, format!("expected '{}', found end of file", expected))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, Error> {
+        self.skip_whitespace();
+        let line = self.line;
+
+        let kind = match self.peek() {
+            Some('{') => self.object()?,
+            Some('[') => self.array()?,
+            Some('"') => Kind::String(self.string()?),
+            Some('t') => self.keyword("true", Kind::Bool(true))?,
+            Some('f') => self.keyword("false", Kind::Bool(false))?,
+            Some('n') => self.keyword("null", Kind::Null)?,
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number()?,
+            Some(c) => return Err(Error::new(line, format!("unexpected '{}'", c))),
+            None => return Err(Error::new(line, "unexpected end of file")),
+        };
+
+        Ok(Value { line, kind })
+    }
+
+    fn keyword(&mut self, word: &str, kind: Kind) -> Result<Kind, Error> {
+        // Reading past a cut off word can run into the next line
+        let line = self.line;
+        for expected in word.chars() {
+            if self.next() != Some(expected) {
+                return Err(Error::new(line, format!("invalid literal, expected {}", word)));
+            }
+        }
+        Ok(kind)
+    }
+
+    fn number(&mut self) -> Result<Kind, Error> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E') {
+                break;
+            }
+            self.next();
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse::<f64>()
+            .map(Kind::Number)
+            .map_err(|_| Error::new(self.line, format!("invalid number {}", text)))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect('"')?;
+        let line = self.line;
+
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some(c) => return Err(Error::new(self.line, format!("unsupported escape \\{}", c))),
+                    None => return Err(Error::new(line, "unterminated string")),
+                },
+                Some('\n') | None => return Err(Error::new(line, "unterminated string")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Kind, Error> {
+        self.expect('[')?;
+
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(Kind::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Kind::Array(values)),
+                _ => return Err(Error::new(self.line, "expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Kind, Error> {
+        self.expect('{')?;
+
+        let mut fields: Vec<(String, Value)> = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(Kind::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let line = self.line;
+            let name = self.string()?;
+            if fields.iter().any(|(other, _)| *other == name) {
+                return Err(Error::new(line, format!("duplicate field \"{}\"", name)));
+            }
+
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((name, self.value()?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Kind::Object(fields)),
+                _ => return Err(Error::new(self.line, "expected ',' or '}' in object")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of(text: &str) -> String {
+        parse(text).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn nested_values_keep_their_lines() {
+        let document = parse("{\n  \"a\": [1, { \"b\": [true, null] }],\n  \"c\": {\n    \"d\": \"e\"\n  }\n}").unwrap();
+        let a = document.field("a").unwrap().as_array().unwrap();
+        assert_eq!(a[0].as_f64().unwrap(), 1.0);
+        let b = a[1].field("b").unwrap().as_array().unwrap();
+        assert!(b[0].as_bool().unwrap());
+        assert_eq!(b[1].type_name(), "null");
+
+        let c = document.field("c").unwrap();
+        assert_eq!(c.line, 3);
+        let d = c.field("d").unwrap();
+        assert_eq!((d.line, d.as_str().unwrap()), (4, "e"));
+    }
+
+    #[test]
+    fn escapes_are_decoded() {
+        let value = parse(r#""say \"hi\" \\ a\/b\n\t""#).unwrap();
+        assert_eq!(value.as_str().unwrap(), "say \"hi\" \\ a/b\n\t");
+        assert_eq!(error_of(r#""\u0041""#), "line 1: unsupported escape \\u");
+        assert_eq!(error_of("[\n  \"open\n]"), "line 2: unterminated string");
+    }
+
+    #[test]
+    fn numbers_parse_like_rust_floats() {
+        for (text, number) in [("0", 0.0), ("-12", -12.0), ("3.25", 3.25), ("1e3", 1000.0), ("-2.5E-1", -0.25)] {
+            assert_eq!(parse(text).unwrap().as_f64().unwrap(), number, "{}", text);
+        }
+        assert_eq!(error_of("1-2"), "line 1: invalid number 1-2");
+        assert_eq!(error_of("[-]"), "line 1: invalid number -");
+
+        assert_eq!(parse("7").unwrap().as_u32().unwrap(), 7);
+        assert!(parse("-1").unwrap().as_u32().is_err());
+        assert!(parse("2.5").unwrap().as_u32().is_err());
+    }
+
+    #[test]
+    fn trailing_commas_are_rejected() {
+        assert_eq!(error_of("[\n  1,\n  2,\n]"), "line 4: unexpected ']'");
+        assert_eq!(error_of("{\n  \"a\": 1,\n}"), "line 3: expected '\"', found '}'");
+    }
+
+    #[test]
+    fn errors_name_the_line_they_are_on() {
+        assert_eq!(error_of("{\n  \"a\": 1\n  \"b\": 2\n}"), "line 3: expected ',' or '}' in object");
+        assert_eq!(error_of("{\n  \"a\": 1,\n  \"a\": 2\n}"), "line 3: duplicate field \"a\"");
+        assert_eq!(error_of("{}\n\nx"), "line 3: unexpected text after the end of the document");
+        assert_eq!(error_of("[1,\n"), "line 2: unexpected end of file");
+        assert_eq!(error_of("[\n  tru\n]"), "line 2: invalid literal, expected true");
+
+        let document = parse("{\n  \"known\": 1,\n  \"typo\": 2\n}").unwrap();
+        assert_eq!(document.check_fields(&["known"]).err().unwrap().line, 3);
+        assert_eq!(document.field("missing").err().unwrap().to_string(), "line 1: missing field \"missing\"");
+        assert_eq!(document.field("known").unwrap().as_str().err().unwrap().to_string(), "line 2: expected string, found number");
+    }
+}
@@ -17,32 +17,98 @@
 // along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
 //
 
+// Collision modules and what they depend on are public for benches/collisions.rs
 mod game;
-mod entity;
+mod world;
+pub mod controls;
+pub mod entity;
+mod drawable;
+mod rng;
 mod player;
 mod enemy;
+mod behaviour;
 mod projectile;
 mod game_over;
 mod explosion;
 mod cloud;
+mod recording;
+mod replay;
+mod menu;
+mod overlay;
+mod pause_menu;
+mod settings;
+mod mode;
+mod title;
+mod load_error;
+mod high_scores;
+mod storage;
+mod json;
+mod stage;
+mod path;
+mod squadron;
+mod boss;
+mod pattern;
+mod aim;
+mod pickup;
+mod weapon;
+mod homing;
+mod blast;
+mod grid;
+pub mod collision;
+pub mod collider;
+pub mod vec2;
 
-use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
 use flask::{
     game_context::GameContext,
     palette,
+    scene::Scene,
 };
 
 use crate::game::Game;
+use crate::load_error::LoadError;
+use crate::recording::Recording;
+use crate::replay::Replay;
+use crate::title::Title;
+use crate::world::RunConfig;
 
 pub const WINDOW_WIDTH: u32 = 256;
 pub const WINDOW_HEIGHT: u32 = 256;
 pub const FULLSCREEN: bool = false;
 
-#[wasm_bindgen(start)]
+#[wasm_bindgen]
 pub fn start() {
-    let game = Box::new(Game::new());
+    run_scene(checked(|| Box::new(Title::new())));
+}
+
+#[wasm_bindgen]
+pub fn start_with_seed(seed: u64) {
+    run_scene(checked(|| Box::new(Game::new(RunConfig::new(seed)))));
+}
+
+#[wasm_bindgen]
+pub fn start_replay(replay: &[u8]) -> Result<(), JsValue> {
+    let recording = Recording::from_bytes(replay).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    run_scene(checked(|| Box::new(Replay::new(recording))));
+    Ok(())
+}
+
+// Replay of the last finished run, if any
+#[wasm_bindgen]
+pub fn last_replay() -> Option<Vec<u8>> {
+    recording::last()
+}
+
+// Stage data is compiled in, but if it's broken anyway the game says so instead of crashing on the first run
+fn checked(scene: impl FnOnce() -> Box<dyn Scene>) -> Box<dyn Scene> {
+    match stage::builtin() {
+        Ok(_) => scene(),
+        Err(error) => Box::new(LoadError::new(&error)),
+    }
+}
 
-    if let Err(error) = GameContext::run(WINDOW_WIDTH, WINDOW_HEIGHT, FULLSCREEN, palette::flask_default(), game) {
+fn run_scene(scene: Box<dyn Scene>) {
+    if let Err(error) = GameContext::run(WINDOW_WIDTH, WINDOW_HEIGHT, FULLSCREEN, palette::flask_default(), scene) {
         flask::log(format!("Flask error:\n{}", error).as_str());
     };
 }
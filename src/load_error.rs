@@ -0,0 +1,94 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::scene::Scene;
+use flask::renderer::Renderer;
+use flask::game_status::GameStatus;
+use flask::input::Input;
+use flask::font::Font;
+use flask::palette::FlaskColor;
+
+use crate::controls::Controls;
+
+// Characters per line, the 3x5 font is 4 pixels wide with spacing
+const LINE_LENGTH: usize = 60;
+
+// Shown instead of the game when its stage data doesn't load, so the problem ends up on screen
+// rather than in a crash. Escape quits.
+pub struct LoadError {
+    lines: Vec<String>,
+    font: Font,
+}
+
+impl LoadError {
+    pub fn new(message: &str) -> LoadError {
+        LoadError {
+            lines: wrap(&message.to_uppercase(), LINE_LENGTH),
+            font: Font::load_3x5().unwrap(),
+        }
+    }
+}
+
+impl Scene for LoadError {
+    fn on_start(&mut self, renderer: &mut Renderer) {
+        renderer.set_background_color(FlaskColor::White as u8).unwrap();
+    }
+
+    fn on_update(&mut self, game_status: &mut GameStatus, renderer: &mut Renderer, input: &Input, _delta_time: f64) -> Option<Box<dyn Scene>> {
+        renderer.clear_screen();
+
+        let title = String::from("GAME DATA FAILED TO LOAD");
+        renderer.text(&title, &self.font, -(4 * (title.len() as i64 / 2)), 20, FlaskColor::Red as u8);
+        for (i, line) in self.lines.iter().enumerate() {
+            renderer.text(line, &self.font, -(4 * (line.len() as i64 / 2)), -10 * i as i64, FlaskColor::Purple as u8);
+        }
+
+        let quit_text = String::from("ESC: QUIT");
+        renderer.text(&quit_text, &self.font, -(4 * (quit_text.len() as i64 / 2)), -10 * (self.lines.len() as i64 + 2), FlaskColor::Red as u8);
+
+        if Controls::from_input(input).escape {
+            game_status.quit();
+        }
+
+        None
+    }
+
+    fn on_destroy(&mut self) {
+
+    }
+}
+
+// Breaks text between words into lines of at most `width` characters. Longer words get a line of their own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,97 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::renderer::Renderer;
+use flask::font::Font;
+use flask::palette::FlaskColor;
+
+use crate::controls::Controls;
+
+const LINE_HEIGHT: i64 = 10;
+const CHAR_WIDTH: i64 = 4;
+
+// Vertical list of options navigated with W/S and picked with Enter
+pub struct Menu {
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(items: &[&str]) -> Menu {
+        Menu {
+            items: items.iter().map(|item| String::from(*item)).collect(),
+            selected: 0,
+        }
+    }
+
+    pub fn set_item(&mut self, index: usize, text: String) {
+        self.items[index] = text;
+    }
+
+    pub fn height(&self) -> i64 {
+        self.items.len() as i64 * LINE_HEIGHT
+    }
+
+    // Returns index of the item picked this frame, if any
+    pub fn on_update(&mut self, pressed: &Controls) -> Option<usize> {
+        if self.items.is_empty() {
+            return None;
+        }
+
+        if pressed.up {
+            self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+        }
+        if pressed.down {
+            self.selected = (self.selected + 1) % self.items.len();
+        }
+
+        match pressed.enter {
+            true => Some(self.selected),
+            false => None,
+        }
+    }
+
+    // Draws items centered around x, starting at top
+    pub fn on_draw(&self, renderer: &mut Renderer, font: &Font, x: i64, top: i64) {
+        for (i, item) in self.items.iter().enumerate() {
+            let item_x = x - CHAR_WIDTH * (item.len() as i64 / 2);
+            let item_y = top - LINE_HEIGHT * (i as i64 + 1);
+
+            let color = match i == self.selected {
+                true => FlaskColor::Red,
+                false => FlaskColor::Purple,
+            };
+            if i == self.selected {
+                renderer.circle_filled(item_x - 6, item_y + 2, 1, FlaskColor::Red as u8);
+            }
+            renderer.text(item, font, item_x, item_y, color as u8);
+        }
+    }
+}
+
+// Plain filled box to put menus on top of the game
+pub fn panel(renderer: &mut Renderer, x: i64, y: i64, width: i64, height: i64) {
+    for row in 0..height {
+        renderer.line(x, y + row, x + width, y + row, FlaskColor::White as u8);
+    }
+    renderer.line(x, y, x + width, y, FlaskColor::Purple as u8);
+    renderer.line(x, y + height, x + width, y + height, FlaskColor::Purple as u8);
+    renderer.line(x, y, x, y + height, FlaskColor::Purple as u8);
+    renderer.line(x + width, y, x + width, y + height, FlaskColor::Purple as u8);
+}
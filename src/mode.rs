@@ -0,0 +1,78 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    Normal,
+    // Single life and a faster game
+    Hardcore,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 2] = [Mode::Normal, Mode::Hardcore];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Normal => "NORMAL",
+            Mode::Hardcore => "HARDCORE",
+        }
+    }
+
+    pub fn next(&self) -> Mode {
+        let index = Mode::ALL.iter().position(|mode| mode == self).unwrap();
+        Mode::ALL[(index + 1) % Mode::ALL.len()]
+    }
+
+    pub fn lives(&self) -> u8 {
+        match self {
+            Mode::Normal => 3,
+            Mode::Hardcore => 1,
+        }
+    }
+
+    pub fn game_speed(&self) -> f64 {
+        match self {
+            Mode::Normal => 10.0,
+            Mode::Hardcore => 12.0,
+        }
+    }
+
+    // Scales how well enemy gunners lead and aim their shots
+    pub fn aim_skill(&self) -> f64 {
+        match self {
+            Mode::Normal => 1.0,
+            Mode::Hardcore => 1.5,
+        }
+    }
+
+    pub fn to_id(&self) -> u8 {
+        match self {
+            Mode::Normal => 0,
+            Mode::Hardcore => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Mode> {
+        match id {
+            0 => Some(Mode::Normal),
+            1 => Some(Mode::Hardcore),
+            _ => None,
+        }
+    }
+}
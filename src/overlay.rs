@@ -0,0 +1,86 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::scene::Scene;
+use flask::renderer::Renderer;
+use flask::font::Font;
+
+use crate::controls::Controls;
+
+pub enum Action {
+    Stay,
+    Push(Box<dyn Overlay>),
+    Pop,
+    Switch(Box<dyn Scene>),
+}
+
+// Menu-like layer drawn over a scene. Only the top layer receives input.
+pub trait Overlay {
+    fn on_update(&mut self, pressed: &Controls) -> Action;
+
+    fn on_draw(&self, renderer: &mut Renderer, font: &Font);
+}
+
+// Stack of overlays owned by a scene. Flask runs a single scene at a time, so layering is handled here:
+// the scene keeps drawing its own (frozen) state and the stack goes on top of it.
+pub struct OverlayStack {
+    overlays: Vec<Box<dyn Overlay>>,
+}
+
+impl OverlayStack {
+    pub fn new() -> OverlayStack {
+        OverlayStack {
+            overlays: vec![],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overlays.is_empty()
+    }
+
+    pub fn push(&mut self, overlay: Box<dyn Overlay>) {
+        self.overlays.push(overlay);
+    }
+
+    pub fn on_update(&mut self, pressed: &Controls) -> Option<Box<dyn Scene>> {
+        let action = match self.overlays.last_mut() {
+            Some(overlay) => overlay.on_update(pressed),
+            None => return None,
+        };
+
+        match action {
+            Action::Stay => None,
+            Action::Push(overlay) => {
+                self.overlays.push(overlay);
+                None
+            }
+            Action::Pop => {
+                self.overlays.pop();
+                None
+            }
+            Action::Switch(scene) => Some(scene),
+        }
+    }
+
+    pub fn on_draw(&self, renderer: &mut Renderer, font: &Font) {
+        for overlay in &self.overlays {
+            overlay.on_draw(renderer, font);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use std::rc::Rc;
+
+use crate::json::{self, Error, Value};
+use crate::vec2::Vec2;
+
+// Authored flight paths, see assets/paths.json. Coordinates are world pixels, x from -128 to 128
+// and y from 0 (bottom) to 256 (top), so paths usually start above the screen and end off its side.
+const PATHS: &str = include_str!("../assets/paths.json");
+// Straight pieces each curve segment is flattened into for arc-length lookups
+const SAMPLES_PER_SEGMENT: usize = 16;
+
+// Curve flattened into short straight pieces so enemies can move along it at constant speed
+#[derive(PartialEq, Debug)]
+pub struct Spline {
+    samples: Vec<Vec2>,
+    // Distance along the curve at each sample
+    distances: Vec<f64>,
+}
+
+impl Spline {
+    // Passes through every point
+    pub fn catmull_rom(points: &[Vec2]) -> Spline {
+        let mut samples = vec![points[0]];
+        for i in 0..points.len() - 1 {
+            // End points are repeated so the curve reaches them
+            let p0 = points[i.saturating_sub(1)];
+            let p1 = points[i];
+            let p2 = points[i + 1];
+            let p3 = points[(i + 2).min(points.len() - 1)];
+
+            for sample in 1..=SAMPLES_PER_SEGMENT {
+                let t = sample as f64 / SAMPLES_PER_SEGMENT as f64;
+                samples.push(catmull_rom(p0, p1, p2, p3, t));
+            }
+        }
+        Spline::from_samples(samples)
+    }
+
+    // Chain of cubic Bezier segments sharing end points: start, control, control, end, control, control, end...
+    pub fn bezier(points: &[Vec2]) -> Spline {
+        let mut samples = vec![points[0]];
+        for segment in points.windows(4).step_by(3) {
+            for sample in 1..=SAMPLES_PER_SEGMENT {
+                let t = sample as f64 / SAMPLES_PER_SEGMENT as f64;
+                samples.push(bezier(segment[0], segment[1], segment[2], segment[3], t));
+            }
+        }
+        Spline::from_samples(samples)
+    }
+
+    fn from_samples(samples: Vec<Vec2>) -> Spline {
+        let mut distances = Vec::with_capacity(samples.len());
+        let mut total = 0.0;
+        distances.push(total);
+        for pair in samples.windows(2) {
+            total += pair[0].distance(pair[1]);
+            distances.push(total);
+        }
+
+        Spline { samples, distances }
+    }
+
+    pub fn length(&self) -> f64 {
+        *self.distances.last().unwrap()
+    }
+
+    // Point `distance` pixels along the curve. Past the end it carries on in the final direction.
+    pub fn point_at(&self, distance: f64) -> Vec2 {
+        let count = self.samples.len();
+        if count == 1 {
+            return self.samples[0];
+        }
+
+        let distance = distance.max(0.0);
+        let index = match distance >= self.length() {
+            true => count - 2,
+            false => self.distances.partition_point(|d| *d <= distance).max(1) - 1,
+        };
+
+        let (start, end) = (self.samples[index], self.samples[index + 1]);
+        let piece_length = self.distances[index + 1] - self.distances[index];
+        if piece_length == 0.0 {
+            return end;
+        }
+
+        let t = (distance - self.distances[index]) / piece_length;
+        start.lerp(end, t)
+    }
+}
+
+pub struct PathLibrary {
+    paths: Vec<(String, Rc<Spline>)>,
+}
+
+impl PathLibrary {
+    pub fn get(&self, name: &str) -> Option<Rc<Spline>> {
+        self.paths.iter().find(|(path_name, _)| path_name == name).map(|(_, spline)| spline.clone())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.paths.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+pub fn builtin() -> Result<PathLibrary, Error> {
+    load(PATHS)
+}
+
+pub fn load(text: &str) -> Result<PathLibrary, Error> {
+    let document = json::parse(text)?;
+    document.check_fields(&["paths"])?;
+
+    let mut paths = vec![];
+    for (name, path_value) in document.field("paths")?.as_object()? {
+        paths.push((name.clone(), Rc::new(load_path(path_value)?)));
+    }
+
+    Ok(PathLibrary { paths })
+}
+
+fn load_path(value: &Value) -> Result<Spline, Error> {
+    value.check_fields(&["type", "points"])?;
+
+    let points_value = value.field("points")?;
+    let mut points = vec![];
+    for point_value in points_value.as_array()? {
+        match point_value.as_array()? {
+            [x, y] => points.push(Vec2::new(x.as_f64()?, y.as_f64()?)),
+            _ => return Err(Error::new(point_value.line, "point must be [x, y]")),
+        }
+    }
+
+    let type_value = value.field("type")?;
+    match type_value.as_str()? {
+        "catmull_rom" => {
+            if points.len() < 2 {
+                return Err(Error::new(points_value.line, "catmull_rom path needs at least 2 points"));
+            }
+            Ok(Spline::catmull_rom(&points))
+        }
+        "bezier" => {
+            if points.len() < 4 || (points.len() - 1) % 3 != 0 {
+                return Err(Error::new(points_value.line, format!(
+                    "bezier path needs 4, 7, 10... points (end, control, control, end, ...), found {}",
+                    points.len()
+                )));
+            }
+            Ok(Spline::bezier(&points))
+        }
+        other => Err(Error::new(type_value.line, format!("unknown path type \"{}\", expected catmull_rom or bezier", other))),
+    }
+}
+
+fn catmull_rom(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f64) -> Vec2 {
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * (t * t)
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * (t * t * t)) * 0.5
+}
+
+fn bezier(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f64) -> Vec2 {
+    let u = 1.0 - t;
+    p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t * t) + p3 * (t * t * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Chords between points a fixed distance apart along the curve
+    fn chords(spline: &Spline, step: f64) -> Vec<f64> {
+        let steps = (spline.length() / step) as usize;
+        (0..steps).map(|i| spline.point_at(i as f64 * step).distance(spline.point_at((i + 1) as f64 * step))).collect()
+    }
+
+    fn assert_even(chords: &[f64], step: f64) {
+        assert!(chords.len() > 10);
+        for chord in chords {
+            // A chord is never longer than the arc it cuts, and only a little shorter on gentle curves
+            assert!(*chord <= step + 1e-9 && *chord > step * 0.95, "chord {} for step {}", chord, step);
+        }
+    }
+
+    // Control points bunched up at the start and spread out later, so parameter steps would be anything but even
+    #[test]
+    fn catmull_rom_moves_at_constant_speed() {
+        let points = [Vec2::new(0.0, 0.0), Vec2::new(10.0, 20.0), Vec2::new(20.0, 30.0), Vec2::new(90.0, 50.0), Vec2::new(160.0, 20.0)];
+        let spline = Spline::catmull_rom(&points);
+        assert_even(&chords(&spline, 4.0), 4.0);
+        assert_eq!(spline.point_at(0.0), points[0]);
+        assert!(spline.point_at(spline.length()).distance(points[4]) < 1e-9);
+    }
+
+    #[test]
+    fn bezier_moves_at_constant_speed() {
+        let points = [Vec2::new(0.0, 0.0), Vec2::new(0.0, 100.0), Vec2::new(10.0, 100.0), Vec2::new(100.0, 100.0)];
+        let spline = Spline::bezier(&points);
+        assert_even(&chords(&spline, 4.0), 4.0);
+    }
+
+    #[test]
+    fn carries_on_past_the_end() {
+        let spline = Spline::catmull_rom(&[Vec2::new(0.0, 0.0), Vec2::new(0.0, -10.0)]);
+        assert!(spline.point_at(15.0).distance(Vec2::new(0.0, -15.0)) < 1e-9);
+    }
+}
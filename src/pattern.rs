@@ -0,0 +1,353 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use crate::aim::{Aim, Target};
+use crate::rng::Rng;
+use crate::vec2::Vec2;
+
+// Straight down the screen, where rings and spirals start from
+const DOWN: f64 = -90.0;
+const DEFAULT_SPEED: f64 = 10.0;
+
+// What an enemy fires each time its gun goes off. Angles are in degrees and delays in game time units.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Pattern {
+    // `count` shots fanned evenly over `arc` degrees around the player
+    Spread { count: u32, arc: f64, speed: f64 },
+    // `count` shots evenly around a full circle, turned `rotation` degrees further every volley
+    Ring { count: u32, rotation: f64, speed: f64 },
+    // `count` shots `delay` apart, each turned `rotation` degrees from the last, carrying on next volley
+    Spiral { count: u32, rotation: f64, delay: f64, speed: f64 },
+    // `count` shots at random angles within `arc` degrees around the player
+    Spray { count: u32, arc: f64, speed: f64 },
+    // Fires `pattern` `count` times, `delay` apart
+    Burst { count: u32, delay: f64, pattern: Box<Pattern> },
+    // `count` homing missiles launched in a fan over `arc` degrees around the player
+    Missiles { count: u32, arc: f64, speed: f64 },
+    // All of them at once
+    Combo(Vec<Pattern>),
+}
+
+// Shot a pattern queued up. Aimed shots are relative to the direction of the player when they leave.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Shot {
+    pub angle: f64,
+    pub speed: f64,
+    pub delay: f64,
+    pub aimed: bool,
+    pub homing: bool,
+}
+
+impl Pattern {
+    pub const NAMES: [&'static str; 8] = ["aimed", "spread", "ring", "spiral", "spray", "burst", "missiles", "combo"];
+
+    // Pattern with its default parameters. Combos start out empty.
+    pub fn from_name(name: &str) -> Option<Pattern> {
+        match name {
+            "aimed" => Some(Pattern::aimed()),
+            "spread" => Some(Pattern::Spread { count: 3, arc: 30.0, speed: DEFAULT_SPEED }),
+            "ring" => Some(Pattern::Ring { count: 8, rotation: 11.25, speed: 8.0 }),
+            "spiral" => Some(Pattern::Spiral { count: 6, rotation: 20.0, delay: 1.0, speed: 8.0 }),
+            "spray" => Some(Pattern::Spray { count: 4, arc: 40.0, speed: DEFAULT_SPEED }),
+            "burst" => Some(Pattern::Burst { count: 3, delay: 1.5, pattern: Box::new(Pattern::aimed()) }),
+            "missiles" => Some(Pattern::Missiles { count: 2, arc: 60.0, speed: 6.0 }),
+            "combo" => Some(Pattern::Combo(vec![])),
+            _ => None,
+        }
+    }
+
+    // Single shot straight at the player
+    pub fn aimed() -> Pattern {
+        Pattern::Spread { count: 1, arc: 0.0, speed: DEFAULT_SPEED }
+    }
+
+    pub fn parameter_names(&self) -> &'static [&'static str] {
+        match self {
+            Pattern::Spread { .. } => &["count", "arc", "speed"],
+            Pattern::Ring { .. } => &["count", "rotation", "speed"],
+            Pattern::Spiral { .. } => &["count", "rotation", "delay", "speed"],
+            Pattern::Spray { .. } => &["count", "arc", "speed"],
+            Pattern::Burst { .. } => &["count", "delay"],
+            Pattern::Missiles { .. } => &["count", "arc", "speed"],
+            Pattern::Combo(_) => &[],
+        }
+    }
+
+    // Returns false if this pattern has no such parameter
+    pub fn set_parameter(&mut self, name: &str, value: f64) -> bool {
+        let parameter = match (self, name) {
+            (Pattern::Spread { count, .. }, "count")
+            | (Pattern::Ring { count, .. }, "count")
+            | (Pattern::Spiral { count, .. }, "count")
+            | (Pattern::Spray { count, .. }, "count")
+            | (Pattern::Burst { count, .. }, "count")
+            | (Pattern::Missiles { count, .. }, "count") => {
+                *count = value as u32;
+                return true;
+            }
+            (Pattern::Spread { arc, .. }, "arc") | (Pattern::Spray { arc, .. }, "arc") | (Pattern::Missiles { arc, .. }, "arc") => arc,
+            (Pattern::Ring { rotation, .. }, "rotation") | (Pattern::Spiral { rotation, .. }, "rotation") => rotation,
+            (Pattern::Spiral { delay, .. }, "delay") | (Pattern::Burst { delay, .. }, "delay") => delay,
+            (Pattern::Spread { speed, .. }, "speed")
+            | (Pattern::Ring { speed, .. }, "speed")
+            | (Pattern::Spiral { speed, .. }, "speed")
+            | (Pattern::Spray { speed, .. }, "speed")
+            | (Pattern::Missiles { speed, .. }, "speed") => speed,
+            _ => return false,
+        };
+        *parameter = value;
+        true
+    }
+
+    // Shots of the `volley`th time the pattern is fired, counting from 0
+    pub fn volley(&self, volley: u32, rng: &mut Rng) -> Vec<Shot> {
+        let mut shots = vec![];
+        self.push_shots(volley, 0.0, rng, &mut shots);
+        shots
+    }
+
+    fn push_shots(&self, volley: u32, delay: f64, rng: &mut Rng, shots: &mut Vec<Shot>) {
+        match self {
+            Pattern::Spread { count, arc, speed } => {
+                for i in 0..*count {
+                    let angle = fan(i, *count, *arc);
+                    shots.push(Shot { angle, speed: *speed, delay, aimed: true, homing: false });
+                }
+            }
+            Pattern::Ring { count, rotation, speed } => {
+                for i in 0..*count {
+                    let angle = DOWN + volley as f64 * rotation + 360.0 * i as f64 / *count as f64;
+                    shots.push(Shot { angle, speed: *speed, delay, aimed: false, homing: false });
+                }
+            }
+            Pattern::Spiral { count, rotation, delay: gap, speed } => {
+                for i in 0..*count {
+                    let angle = DOWN + (volley * count + i) as f64 * rotation;
+                    shots.push(Shot { angle, speed: *speed, delay: delay + i as f64 * gap, aimed: false, homing: false });
+                }
+            }
+            Pattern::Spray { count, arc, speed } => {
+                for _ in 0..*count {
+                    let angle = (rng.next_f64() - 0.5) * arc;
+                    shots.push(Shot { angle, speed: *speed, delay, aimed: true, homing: false });
+                }
+            }
+            Pattern::Burst { count, delay: gap, pattern } => {
+                for i in 0..*count {
+                    pattern.push_shots(volley * count + i, delay + i as f64 * gap, rng, shots);
+                }
+            }
+            Pattern::Missiles { count, arc, speed } => {
+                for i in 0..*count {
+                    let angle = fan(i, *count, *arc);
+                    shots.push(Shot { angle, speed: *speed, delay, aimed: true, homing: true });
+                }
+            }
+            Pattern::Combo(patterns) => {
+                for pattern in patterns {
+                    pattern.push_shots(volley, delay, rng, shots);
+                }
+            }
+        }
+    }
+}
+
+// Angle of the `i`th of `count` shots spread evenly over `arc`, centred on 0
+fn fan(i: u32, count: u32, arc: f64) -> f64 {
+    match count {
+        1 => 0.0,
+        _ => -arc / 2.0 + arc * i as f64 / (count - 1) as f64,
+    }
+}
+
+// Projectile leaving the gun
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bullet {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub homing: bool,
+}
+
+// Fires a pattern every `interval` and lets its delayed shots out as they come due.
+// Doesn't touch the world, so what it emits can be checked on its own.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    pattern: Pattern,
+    aim: Aim,
+    interval: f64,
+    timer: f64,
+    volley: u32,
+    pending: Vec<Shot>,
+}
+
+impl Emitter {
+    pub fn new(pattern: Pattern, aim: Aim, interval: f64) -> Emitter {
+        Emitter {
+            pattern,
+            aim,
+            interval,
+            timer: interval,
+            volley: 0,
+            pending: vec![],
+        }
+    }
+
+    // Bullets leaving `origin` during this step. The trigger is only pulled while `can_fire`,
+    // but shots already queued still go out.
+    pub fn update(&mut self, step: f64, origin: Vec2, target: Target, can_fire: bool, rng: &mut Rng) -> Vec<Bullet> {
+        self.timer -= step;
+        if can_fire && self.timer < 0.0 {
+            self.pending.extend(self.pattern.volley(self.volley, rng));
+            self.volley += 1;
+            self.timer = self.interval;
+        }
+
+        // Shots leaving together miss together, so spreads keep their shape
+        let mut error = None;
+        let mut bullets = vec![];
+        for shot in &mut self.pending {
+            shot.delay -= step;
+            if shot.delay <= 0.0 {
+                let direction = match shot.aimed {
+                    true => {
+                        let error = *error.get_or_insert_with(|| self.aim.random_error(rng));
+                        self.aim.direction(origin, target, shot.speed).rotate(error + shot.angle)
+                    }
+                    false => Vec2::from_angle(shot.angle),
+                };
+                bullets.push(Bullet { position: origin, velocity: direction * shot.speed, homing: shot.homing });
+            }
+        }
+        self.pending.retain(|shot| shot.delay > 0.0);
+
+        bullets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn angles(shots: &[Shot]) -> Vec<f64> {
+        shots.iter().map(|shot| shot.angle).collect()
+    }
+
+    fn delays(shots: &[Shot]) -> Vec<f64> {
+        shots.iter().map(|shot| shot.delay).collect()
+    }
+
+    fn assert_all_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} is not {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} is not {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn spread_fans_evenly_around_the_player() {
+        let mut rng = Rng::new_with_seed(1);
+        let spread = Pattern::Spread { count: 5, arc: 40.0, speed: 10.0 };
+        let shots = spread.volley(0, &mut rng);
+        assert_all_close(&angles(&shots), &[-20.0, -10.0, 0.0, 10.0, 20.0]);
+        assert!(shots.iter().all(|shot| shot.aimed && !shot.homing && shot.delay == 0.0));
+
+        assert_all_close(&angles(&Pattern::aimed().volley(3, &mut rng)), &[0.0]);
+    }
+
+    #[test]
+    fn ring_turns_every_volley() {
+        let mut rng = Rng::new_with_seed(1);
+        let ring = Pattern::Ring { count: 4, rotation: 10.0, speed: 8.0 };
+        assert_all_close(&angles(&ring.volley(0, &mut rng)), &[-90.0, 0.0, 90.0, 180.0]);
+        assert_all_close(&angles(&ring.volley(2, &mut rng)), &[-70.0, 20.0, 110.0, 200.0]);
+        assert!(ring.volley(1, &mut rng).iter().all(|shot| !shot.aimed));
+    }
+
+    #[test]
+    fn spiral_spaces_shots_and_carries_on_next_volley() {
+        let mut rng = Rng::new_with_seed(1);
+        let spiral = Pattern::Spiral { count: 3, rotation: 20.0, delay: 1.5, speed: 8.0 };
+        let shots = spiral.volley(1, &mut rng);
+        assert_all_close(&angles(&shots), &[-30.0, -10.0, 10.0]);
+        assert_all_close(&delays(&shots), &[0.0, 1.5, 3.0]);
+    }
+
+    #[test]
+    fn nested_bursts_add_up_delays_and_volleys() {
+        let mut rng = Rng::new_with_seed(1);
+        let ring = Pattern::Ring { count: 1, rotation: 10.0, speed: 8.0 };
+        let inner = Pattern::Burst { count: 3, delay: 0.5, pattern: Box::new(ring) };
+        let outer = Pattern::Burst { count: 2, delay: 2.0, pattern: Box::new(inner) };
+
+        let shots = outer.volley(0, &mut rng);
+        assert_all_close(&delays(&shots), &[0.0, 0.5, 1.0, 2.0, 2.5, 3.0]);
+        assert_all_close(&angles(&shots), &[-90.0, -80.0, -70.0, -60.0, -50.0, -40.0]);
+
+        // Second volley picks up the ring where the first one left it
+        assert_all_close(&angles(&outer.volley(1, &mut rng))[..1], &[-30.0]);
+    }
+
+    #[test]
+    fn combo_fires_everything_at_once() {
+        let mut rng = Rng::new_with_seed(1);
+        let combo = Pattern::Combo(vec![
+            Pattern::Ring { count: 2, rotation: 0.0, speed: 8.0 },
+            Pattern::Missiles { count: 1, arc: 0.0, speed: 6.0 },
+        ]);
+        let shots = combo.volley(0, &mut rng);
+        assert_eq!(shots.len(), 3);
+        assert_all_close(&angles(&shots[..2]), &[-90.0, 90.0]);
+        assert!(shots[2].aimed && shots[2].homing);
+        assert!(shots.iter().all(|shot| shot.delay == 0.0));
+    }
+
+    #[test]
+    fn emitter_lets_delayed_shots_out_as_they_come_due() {
+        let mut rng = Rng::new_with_seed(1);
+        let spiral = Pattern::Spiral { count: 3, rotation: 20.0, delay: 2.0, speed: 8.0 };
+        let mut emitter = Emitter::new(spiral, Aim { lead: 0.0, error: 0.0 }, 1.0);
+        let origin = Vec2::new(0.0, 100.0);
+        let target = Target { position: Vec2::ZERO, velocity: Vec2::ZERO };
+
+        let bullet_angles = |bullets: Vec<Bullet>| bullets.iter().map(|bullet| bullet.velocity.angle()).collect::<Vec<f64>>();
+        assert_all_close(&bullet_angles(emitter.update(1.5, origin, target, true, &mut rng)), &[-90.0]);
+        // Trigger is off, but shots already queued still go out
+        assert_all_close(&bullet_angles(emitter.update(0.5, origin, target, false, &mut rng)), &[-70.0]);
+        assert!(emitter.update(0.5, origin, target, false, &mut rng).is_empty());
+        assert_all_close(&bullet_angles(emitter.update(1.5, origin, target, false, &mut rng)), &[-50.0]);
+        assert!(emitter.update(5.0, origin, target, false, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn emitter_aims_a_volley_as_one() {
+        let mut rng = Rng::new_with_seed(1);
+        let spread = Pattern::Spread { count: 3, arc: 20.0, speed: 10.0 };
+        let mut emitter = Emitter::new(spread, Aim { lead: 0.0, error: 5.0 }, 1.0);
+        let origin = Vec2::new(0.0, 100.0);
+        let target = Target { position: Vec2::ZERO, velocity: Vec2::ZERO };
+
+        let bullets = emitter.update(1.5, origin, target, true, &mut rng);
+        assert_eq!(bullets.len(), 3);
+        // Same miss for every shot, so the fan keeps its shape around the player
+        let error = bullets[1].velocity.angle() + 90.0;
+        assert!(error.abs() <= 5.0);
+        assert_all_close(&[bullets[0].velocity.angle(), bullets[2].velocity.angle()], &[-100.0 + error, -80.0 + error]);
+        assert!(bullets.iter().all(|bullet| (bullet.velocity.length() - 10.0).abs() < 1e-9));
+    }
+}
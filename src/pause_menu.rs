@@ -0,0 +1,77 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::renderer::Renderer;
+use flask::font::Font;
+use flask::palette::FlaskColor;
+
+use crate::controls::Controls;
+use crate::game::Game;
+use crate::menu::{self, Menu};
+use crate::overlay::{Action, Overlay};
+use crate::settings::SettingsMenu;
+use crate::title::Title;
+use crate::world::RunConfig;
+
+const RESUME: usize = 0;
+const RESTART: usize = 1;
+const SETTINGS: usize = 2;
+const QUIT: usize = 3;
+
+pub struct PauseMenu {
+    menu: Menu,
+    // Run being paused, RESTART starts it over with the same seed and mode
+    config: RunConfig,
+}
+
+impl PauseMenu {
+    pub fn new(config: RunConfig) -> PauseMenu {
+        PauseMenu {
+            menu: Menu::new(&["RESUME", "RESTART", "SETTINGS", "QUIT"]),
+            config,
+        }
+    }
+}
+
+impl Overlay for PauseMenu {
+    fn on_update(&mut self, pressed: &Controls) -> Action {
+        if pressed.escape {
+            return Action::Pop;
+        }
+
+        match self.menu.on_update(pressed) {
+            Some(RESUME) => Action::Pop,
+            Some(RESTART) => Action::Switch(Box::new(Game::new(self.config))),
+            Some(SETTINGS) => Action::Push(Box::new(SettingsMenu::new())),
+            Some(QUIT) => Action::Switch(Box::new(Title::new())),
+            _ => Action::Stay,
+        }
+    }
+
+    fn on_draw(&self, renderer: &mut Renderer, font: &Font) {
+        let (_, window_h) = renderer.get_window_size();
+        let top = window_h / 2 + 30;
+
+        menu::panel(renderer, -40, top - 15 - self.menu.height() - 5, 80, self.menu.height() + 20);
+
+        let title = String::from("PAUSED");
+        renderer.text(&title, font, -(4 * (title.len() as i64 / 2)), top - 10, FlaskColor::Red as u8);
+        self.menu.on_draw(renderer, font, 0, top - 15);
+    }
+}
@@ -0,0 +1,158 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::renderer::Renderer;
+use flask::palette::FlaskColor;
+use flask::sprite::SpriteBank;
+
+use crate::collision::Layer;
+use crate::controls::Controls;
+use crate::drawable::Drawable;
+use crate::entity::Entity;
+use crate::rng::Rng;
+use crate::vec2::Vec2;
+use crate::weapon::WeaponKind;
+
+const FALL_SPEED: f64 = 1.5;
+const LIFETIME: f64 = 80.0;
+// Starts blinking when this close to disappearing
+const BLINK_TIME: f64 = 25.0;
+// Pickups this close to the player get pulled in
+const MAGNET_RADIUS: f64 = 30.0;
+const MAGNET_SPEED: f64 = 12.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PickupKind {
+    // Powers up the weapon, or switches to another kind
+    Weapon(WeaponKind),
+    ExtraLife,
+    Shield,
+    Bomb,
+    // Just score
+    Medal,
+}
+
+// What an enemy may leave behind: `chance` of dropping anything, then a kind picked by weight
+#[derive(Clone, Copy, Debug)]
+pub struct DropTable {
+    pub chance: f64,
+    pub weights: &'static [(PickupKind, u32)],
+}
+
+impl DropTable {
+    pub fn roll(&self, rng: &mut Rng) -> Option<PickupKind> {
+        if rng.next_f64() >= self.chance {
+            return None;
+        }
+
+        let total: u32 = self.weights.iter().map(|(_, weight)| weight).sum();
+        let mut pick = rng.next_i64_in_range(0, total as i64) as u32;
+        for (kind, weight) in self.weights {
+            if pick < *weight {
+                return Some(*kind);
+            }
+            pick -= weight;
+        }
+        None
+    }
+}
+
+#[derive(Clone)]
+pub struct Pickup {
+    kind: PickupKind,
+    position: Vec2,
+    time_left: f64,
+    // Player position, for the magnet
+    target: Vec2,
+}
+
+impl Pickup {
+    pub fn new(kind: PickupKind, position: Vec2) -> Pickup {
+        Pickup {
+            kind,
+            position,
+            time_left: LIFETIME,
+            target: Vec2::ZERO,
+        }
+    }
+
+    pub fn kind(&self) -> PickupKind {
+        self.kind
+    }
+
+    pub fn set_target(&mut self, target: Vec2) {
+        self.target = target;
+    }
+
+    pub fn expired(&self) -> bool {
+        self.time_left <= 0.0 || self.position.y < -10.0
+    }
+}
+
+impl Entity for Pickup {
+    fn on_update(&mut self, _controls: &Controls, delta_time: f64, game_speed: f64) {
+        let step = game_speed * delta_time;
+        self.time_left -= step;
+
+        let delta = self.target - self.position;
+        let distance = delta.length();
+        if distance < MAGNET_RADIUS && distance > 0.0 {
+            let pull = (MAGNET_SPEED * step).min(distance);
+            self.position += delta.normalised() * pull;
+        } else {
+            self.position.y -= FALL_SPEED * step;
+        }
+    }
+
+    fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    fn collider_radius(&self) -> i64 {
+        4
+    }
+
+    fn layer(&self) -> Option<Layer> {
+        Some(Layer::Pickup)
+    }
+}
+
+impl Drawable for Pickup {
+    fn on_draw(&self, renderer: &mut Renderer, _sprites: &mut SpriteBank, _rng: &mut Rng) {
+        // Blinks when about to go away
+        if self.time_left < BLINK_TIME && (self.time_left / 2.0) as i64 % 2 == 0 {
+            return;
+        }
+
+        let (fill, ring) = match self.kind {
+            PickupKind::Weapon(WeaponKind::Twin) => (FlaskColor::Red, FlaskColor::Yellow),
+            PickupKind::Weapon(WeaponKind::Spread) => (FlaskColor::Yellow, FlaskColor::Red),
+            PickupKind::Weapon(WeaponKind::Laser) => (FlaskColor::White, FlaskColor::Red),
+            PickupKind::Weapon(WeaponKind::Homing) => (FlaskColor::Red, FlaskColor::White),
+            PickupKind::ExtraLife => (FlaskColor::Purple, FlaskColor::White),
+            PickupKind::Shield => (FlaskColor::White, FlaskColor::Purple),
+            PickupKind::Bomb => (FlaskColor::Purple, FlaskColor::Red),
+            PickupKind::Medal => (FlaskColor::Yellow, FlaskColor::White),
+        };
+
+        let (x, y) = self.position.rounded();
+        renderer.circle_filled(x, y, 3, fill as u8);
+        renderer.circle(x, y, 4, ring as u8);
+    }
+}
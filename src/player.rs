@@ -17,45 +17,98 @@
 // along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
 //
 
-use flask::game_status::GameStatus;
 use flask::renderer::Renderer;
-use flask::input::{Input, Key, State};
 use flask::sprite::{SpriteBank, SpriteID};
 use flask::palette::FlaskColor;
-use flask::rand::Rand;
 
+use crate::collision::Layer;
+use crate::controls::Controls;
+use crate::drawable::Drawable;
 use crate::entity::Entity;
-use crate::game::{WALK_AREA_MAX_X, WALK_AREA_MAX_Y};
+use crate::world::{WALK_AREA_MAX_X, WALK_AREA_MAX_Y};
+use crate::projectile::Projectile;
+use crate::rng::Rng;
+use crate::vec2::Vec2;
+use crate::weapon::{Weapon, WeaponKind};
 
-const SHOOT_INTERVAL: f64 = 1.0;
+const MAX_LIVES: u8 = 9;
+const MAX_BOMBS: u8 = 5;
+// Bombs in stock at the start of every stage
+const STARTING_BOMBS: u8 = 2;
+const SHIELD_TIME: f64 = 100.0;
+// Shield ring starts blinking when about to run out
+const SHIELD_BLINK_TIME: f64 = 20.0;
+const SPRITE_ID: SpriteID = SpriteID(0);
 
+// How getting hit plays out. Times are in game time units, distances in pixels. Tune it through HIT_FEEDBACK.
+#[derive(Clone, Copy, Debug)]
+pub struct HitFeedback {
+    // Further hits are ignored for this long, so overlapping bullets cost one life
+    pub invulnerable_time: f64,
+    // Sprite is hidden every other interval while invulnerable. Has to be above zero.
+    pub blink_interval: f64,
+    pub shake_time: f64,
+    pub shake_strength: i64,
+    pub flash_time: f64,
+    // Enemy bullets this close are cleared, so the player isn't hit again the moment it wears off
+    pub clear_radius: f64,
+}
+
+pub const HIT_FEEDBACK: HitFeedback = HitFeedback {
+    invulnerable_time: 20.0,
+    blink_interval: 1.0,
+    shake_time: 3.0,
+    shake_strength: 3,
+    flash_time: 1.5,
+    clear_radius: 40.0,
+};
+
+#[derive(Clone)]
 pub struct Player {
     health: u8,
     speed: f64,
-    position_x: f64,
-    position_y: f64,
-    shoot_timer: f64,
-    sprite_id: SpriteID,
-    rng: Rand,
+    position: Vec2,
+    // Pixels per game time unit, as moved last update
+    velocity: Vec2,
+    weapon: Weapon,
+    // Damage is ignored while this is above zero
+    shield_time: f64,
+    // Damage is also ignored during this, without the shield ring
+    invulnerable_time: f64,
+    bombs: u8,
 }
 
 impl Player {
-    pub fn new() -> Player {
+    pub fn new(lives: u8) -> Player {
         Player {
-            health: 3,
+            health: lives,
             speed: 10.0,
-            position_x: 0.0,
-            position_y: 25.0,
-            shoot_timer: SHOOT_INTERVAL,
-            sprite_id: SpriteID(0),
-            rng: Rand::new()
+            position: Vec2::new(0.0, 25.0),
+            velocity: Vec2::ZERO,
+            weapon: Weapon::new(WeaponKind::Twin),
+            shield_time: 0.0,
+            invulnerable_time: 0.0,
+            bombs: STARTING_BOMBS,
         }
     }
 
-    pub fn damage(&mut self) {
-        if self.health >= 1 {
-            self.health -= 1;
+    // Returns true if it cost a life, false if shielded or still invulnerable from the last hit
+    pub fn damage(&mut self) -> bool {
+        if self.shielded() || self.invulnerable_time > 0.0 || self.health == 0 {
+            return false;
         }
+        self.health -= 1;
+        self.invulnerable_time = HIT_FEEDBACK.invulnerable_time;
+        true
+    }
+
+    // Hidden every other blink interval while invulnerable
+    fn visible(&self) -> bool {
+        !self.invulnerable() || (self.invulnerable_time / HIT_FEEDBACK.blink_interval) as i64 % 2 == 0
+    }
+
+    pub fn invulnerable(&self) -> bool {
+        self.invulnerable_time > 0.0
     }
 
     pub fn health(&self) -> u8 {
@@ -66,87 +119,175 @@ impl Player {
         self.health > 0
     }
 
-    pub fn can_shoot(&self) -> bool {
-        self.shoot_timer < 0.0
+    pub fn extra_life(&mut self) {
+        self.health = (self.health + 1).min(MAX_LIVES);
+    }
+
+    pub fn weapon(&self) -> &Weapon {
+        &self.weapon
+    }
+
+    // Same kind of weapon powers it up, another kind swaps to it
+    pub fn collect_weapon(&mut self, kind: WeaponKind) {
+        match self.weapon.kind() == kind {
+            true => self.weapon.level_up(),
+            false => self.weapon.switch(kind),
+        }
+    }
+
+    pub fn shield(&mut self) {
+        self.shield_time = SHIELD_TIME;
+    }
+
+    pub fn shielded(&self) -> bool {
+        self.shield_time > 0.0
+    }
+
+    pub fn bombs(&self) -> u8 {
+        self.bombs
+    }
+
+    pub fn add_bomb(&mut self) {
+        self.bombs = (self.bombs + 1).min(MAX_BOMBS);
+    }
+
+    // Takes a bomb from stock, false if there are none left
+    pub fn use_bomb(&mut self) -> bool {
+        if self.bombs == 0 {
+            return false;
+        }
+        self.bombs -= 1;
+        true
+    }
+
+    // Empties the bomb stock, returning how many were left, and hands out the next stage's bombs
+    pub fn restock_bombs(&mut self) -> u8 {
+        let unused = self.bombs;
+        self.bombs = STARTING_BOMBS;
+        unused
+    }
+
+    pub fn make_invulnerable(&mut self, time: f64) {
+        self.invulnerable_time = self.invulnerable_time.max(time);
     }
 
-    pub fn reset_shoot_interval(&mut self) {
-        self.shoot_timer = SHOOT_INTERVAL;
+    pub fn velocity(&self) -> Vec2 {
+        self.velocity
+    }
+
+    // Whatever the weapon fires this update
+    pub fn fire(&mut self) -> Vec<Projectile> {
+        self.weapon.fire(self.position)
     }
 }
 
 impl Entity for Player {
-    fn on_update(&mut self, _game_status: &mut GameStatus, renderer: &mut Renderer, sprites: &mut SpriteBank, input: &Input, delta_time: f64, game_speed : f64) {
-        self.shoot_timer -= game_speed * delta_time;
+    fn on_update(&mut self, controls: &Controls, delta_time: f64, game_speed : f64) {
+        self.weapon.update(game_speed * delta_time);
+        self.shield_time -= game_speed * delta_time;
+        self.invulnerable_time -= game_speed * delta_time;
+        let previous = self.position;
 
-        if input.get_key_state(Key::D) == State::Down {
-            self.position_x += self.speed * game_speed * delta_time;
+        let mut direction = Vec2::ZERO;
+        if controls.right {
+            direction.x += 1.0;
         }
-        if input.get_key_state(Key::A) == State::Down {
-            self.position_x -= self.speed * game_speed * delta_time;
+        if controls.left {
+            direction.x -= 1.0;
         }
-
-        // Don't let overflow
-        if self.position_x > WALK_AREA_MAX_X {
-            self.position_x = WALK_AREA_MAX_X;
+        if controls.up {
+            direction.y += 1.0;
         }
-        if self.position_x < -WALK_AREA_MAX_X {
-            self.position_x = -WALK_AREA_MAX_X;
+        if controls.down {
+            direction.y -= 1.0;
         }
 
-        if input.get_key_state(Key::W) == State::Down {
-            self.position_y += self.speed * game_speed * delta_time;
-        }
-        if input.get_key_state(Key::S) == State::Down {
-            self.position_y -= self.speed * game_speed * delta_time;
-        }
+        // Diagonals are no faster than flying straight
+        let step = game_speed * delta_time;
+        self.position += direction.normalised() * (self.speed * step);
 
         // Don't let overflow
-        if self.position_y < 0.0 {
-            self.position_y = 0.0;
-        } else if self.position_y > WALK_AREA_MAX_Y {
-            self.position_y = WALK_AREA_MAX_Y;
+        self.position.x = self.position.x.clamp(-WALK_AREA_MAX_X, WALK_AREA_MAX_X);
+        self.position.y = self.position.y.clamp(0.0, WALK_AREA_MAX_Y);
+
+        // Measured after clamping, so pushing against an edge doesn't count as moving
+        if step > 0.0 {
+            self.velocity = (self.position - previous) * (1.0 / step);
         }
+    }
 
-        let sprite = sprites.get_sprite(&self.sprite_id).unwrap();
+    fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    fn collider_radius(&self) -> i64 {
+        3
+    }
+
+    fn layer(&self) -> Option<Layer> {
+        Some(Layer::Player)
+    }
+}
+
+impl Drawable for Player {
+    fn on_draw(&self, renderer: &mut Renderer, sprites: &mut SpriteBank, rng: &mut Rng) {
+        let sprite = sprites.get_sprite(&SPRITE_ID).unwrap();
 
         let x_offset = (sprite.get_width() / 2) as i64;
         let y_offset = (sprite.get_height() / 2) as i64;
+        let (x, y) = self.position.rounded();
 
-        renderer.sprite(sprite, self.position_x as i64 - x_offset, self.position_y as i64 - y_offset, false);
+        // Blinks while invulnerable, flame and shield still drawn
+        if self.visible() {
+            renderer.sprite(sprite, x - x_offset, y - y_offset, false);
+        }
 
         renderer.line(
-            self.position_x as i64 - 2,
-            self.position_y as i64 - 9,
-            self.position_x as i64 - 2,
-            self.position_y as i64 - 9 - self.rng.next_i64_in_range(0, 5),
+            x - 2,
+            y - 9,
+            x - 2,
+            y - 9 - rng.next_i64_in_range(0, 5),
             FlaskColor::Red as u8
         );
         renderer.line(
-            self.position_x as i64 - 1,
-            self.position_y as i64 - 9,
-            self.position_x as i64 - 1,
-            self.position_y as i64 - 9 - self.rng.next_i64_in_range(2, 7),
+            x - 1,
+            y - 9,
+            x - 1,
+            y - 9 - rng.next_i64_in_range(2, 7),
             FlaskColor::Yellow as u8
         );
         renderer.line(
-            self.position_x as i64,
-            self.position_y as i64 - 9,
-            self.position_x as i64,
-            self.position_y as i64 - 9 - self.rng.next_i64_in_range(0, 5),
+            x,
+            y - 9,
+            x,
+            y - 9 - rng.next_i64_in_range(0, 5),
             FlaskColor::Red as u8
         );
-    }
 
-    fn x(&self) -> i64 {
-        self.position_x as i64
+        if self.shielded() && (self.shield_time > SHIELD_BLINK_TIME || (self.shield_time as i64) % 4 < 2) {
+            renderer.circle(x, y, 9, FlaskColor::White as u8);
+        }
     }
+}
 
-    fn y(&self) -> i64 {
-        self.position_y as i64
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn collider_radius(&self) -> i64 {
-        3
+    // Guards HIT_FEEDBACK, which is used as tuned rather than clamped where it's drawn
+    #[test]
+    fn blinks_while_invulnerable() {
+        let mut player = Player::new(3);
+        assert!(player.visible());
+
+        let (mut shown, mut hidden) = (0, 0);
+        for i in 0..100 {
+            player.invulnerable_time = HIT_FEEDBACK.invulnerable_time * (100 - i) as f64 / 100.0;
+            match player.visible() {
+                true => shown += 1,
+                false => hidden += 1,
+            }
+        }
+        assert!(shown > 0 && hidden > 0);
     }
-}
\ No newline at end of file
+}
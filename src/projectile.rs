@@ -17,57 +17,188 @@
 // along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
 //
 
-use flask::game_status::GameStatus;
 use flask::renderer::Renderer;
-use flask::input::Input;
 use flask::palette::FlaskColor;
 use flask::sprite::SpriteBank;
 
+use crate::collider::Collider;
+use crate::collision::Layer;
+use crate::controls::Controls;
+use crate::drawable::Drawable;
 use crate::entity::Entity;
+use crate::homing::Homing;
+use crate::rng::Rng;
+use crate::vec2::Vec2;
 
+// Missiles leave a puff of smoke this often, and keep this many around
+const SMOKE_INTERVAL: f64 = 0.5;
+const SMOKE_LENGTH: usize = 6;
+// Piercing beams streak this far behind their head, and hit along all of it
+const BEAM_LENGTH: f64 = 8.0;
+
+#[derive(Clone)]
 pub struct Projectile {
-    speed: f64,
-    angle: f64,
-    position_x: f64,
-    position_y: f64,
+    // Pixels per game time unit
+    velocity: Vec2,
+    position: Vec2,
+    // Where it was before the last update, it collides along the whole way from there
+    previous: Vec2,
     color: u8,
+    // Player's shots unless said otherwise
+    layer: Layer,
+    damage: u8,
+    // Flies on through whatever it hits, damaging it every update they overlap
+    piercing: bool,
+    // Guidance for missiles, None for shots that fly straight
+    homing: Option<Homing>,
+    // Recent positions of a missile, oldest first
+    smoke: Vec<Vec2>,
+    smoke_timer: f64,
 }
 
 impl Projectile {
-    pub fn new(speed: f64, angle: f64, position_x: i64, position_y: i64, color: u8) -> Projectile {
+    pub fn new(velocity: Vec2, position: Vec2, color: u8) -> Projectile {
         Projectile {
-            speed,
-            angle,
-            position_x: position_x as f64,
-            position_y: position_y as f64,
-            color
+            velocity,
+            position,
+            previous: position,
+            color,
+            layer: Layer::PlayerBullet,
+            damage: 1,
+            piercing: false,
+            homing: None,
+            smoke: vec![],
+            smoke_timer: 0.0,
         }
     }
-}
 
-impl Entity for Projectile {
-    fn on_update(&mut self, _game_status: &mut GameStatus, renderer: &mut Renderer, _sprites: &mut SpriteBank, _input: &Input, delta_time: f64, game_speed: f64) {
-        let radians = self.angle * (std::f64::consts::PI / 180.0);
+    pub fn on_layer(self, layer: Layer) -> Projectile {
+        Projectile {
+            layer,
+            ..self
+        }
+    }
+
+    pub fn with_damage(self, damage: u8) -> Projectile {
+        Projectile {
+            damage,
+            ..self
+        }
+    }
+
+    pub fn piercing(self) -> Projectile {
+        Projectile {
+            piercing: true,
+            ..self
+        }
+    }
+
+    pub fn homing(self, homing: Homing) -> Projectile {
+        Projectile {
+            homing: Some(homing),
+            ..self
+        }
+    }
 
-        let dir_x = radians.cos();
-        let dir_y = radians.sin();
+    pub fn damage(&self) -> u8 {
+        self.damage
+    }
 
-        self.position_x += dir_x * self.speed * game_speed * delta_time;
-        self.position_y += dir_y * self.speed * game_speed * delta_time;
+    pub fn is_piercing(&self) -> bool {
+        self.piercing
+    }
 
-        renderer.circle(self.position_x as i64, self.position_y as i64, 2, self.color);
-        renderer.circle_filled(self.position_x as i64, self.position_y as i64, 1, FlaskColor::White as u8);
+    pub fn is_homing(&self) -> bool {
+        self.homing.is_some()
     }
 
-    fn x(&self) -> i64 {
-        self.position_x as i64
+    // Lets a missile pick what to chase out of `targets`
+    pub fn seek(&mut self, targets: &[Vec2]) {
+        if let Some(homing) = &mut self.homing {
+            homing.track(self.position, self.velocity, targets);
+        }
     }
 
-    fn y(&self) -> i64 {
-        self.position_y as i64
+    // Missile ran out of lifetime
+    pub fn expired(&self) -> bool {
+        self.homing.map_or(false, |homing| homing.expired())
+    }
+}
+
+impl Entity for Projectile {
+    fn on_update(&mut self, _controls: &Controls, delta_time: f64, game_speed: f64) {
+        let step = game_speed * delta_time;
+        if let Some(homing) = &mut self.homing {
+            // Homing turns the missile, it keeps its speed
+            let speed = self.velocity.length();
+            self.velocity = homing.steer(self.position, self.velocity.normalised(), step) * speed;
+
+            self.smoke_timer -= step;
+            if self.smoke_timer < 0.0 {
+                self.smoke_timer = SMOKE_INTERVAL;
+                // Smoke only while the motor burns, then the trail clears away a puff at a time
+                if homing.has_fuel() {
+                    self.smoke.push(self.position);
+                }
+                if (self.smoke.len() > SMOKE_LENGTH || !homing.has_fuel()) && !self.smoke.is_empty() {
+                    self.smoke.remove(0);
+                }
+            }
+        }
+
+        self.previous = self.position;
+        self.position += self.velocity * step;
+    }
+
+    fn position(&self) -> Vec2 {
+        self.position
     }
 
     fn collider_radius(&self) -> i64 {
         2
     }
-}
\ No newline at end of file
+
+    // Swept from where it was to where it is, so a fast shot can't skip over anything on a slow frame
+    fn collider(&self) -> Collider {
+        let mut end = -self.last_move();
+        if self.piercing {
+            end -= self.velocity.normalised() * BEAM_LENGTH;
+        }
+        Collider::Capsule {
+            start: Vec2::ZERO,
+            end,
+            radius: self.collider_radius() as f64,
+        }
+    }
+
+    fn last_move(&self) -> Vec2 {
+        self.position - self.previous
+    }
+
+    fn layer(&self) -> Option<Layer> {
+        Some(self.layer)
+    }
+}
+
+impl Drawable for Projectile {
+    fn on_draw(&self, renderer: &mut Renderer, _sprites: &mut SpriteBank, _rng: &mut Rng) {
+        let (x, y) = self.position.rounded();
+
+        if self.piercing {
+            // Beam streak trailing behind
+            let (tail_x, tail_y) = (self.position - self.velocity.normalised() * BEAM_LENGTH).rounded();
+            renderer.line(tail_x, tail_y, x, y, self.color);
+            return;
+        }
+
+        // Puffs grow as they drift apart
+        for (i, puff) in self.smoke.iter().enumerate() {
+            let size = (self.smoke.len() - i) as u32 / 2;
+            let (puff_x, puff_y) = puff.rounded();
+            renderer.circle_filled(puff_x, puff_y, size, FlaskColor::White as u8);
+        }
+
+        renderer.circle(x, y, 2, self.color);
+        renderer.circle_filled(x, y, 1, FlaskColor::White as u8);
+    }
+}
@@ -0,0 +1,329 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::controls::Controls;
+use crate::mode::Mode;
+use crate::world::RunConfig;
+
+// Replay file layout, all integers little endian:
+//   magic "SKYR" | format version u16 | game version (u8 length + bytes) | simulation version u16
+//   | seed u64 | mode u8 | flags u8
+//   | final score u128 | frame count u32 | frames (delta time f64 bits + controls u8) | FNV-1a checksum u32
+const MAGIC: &[u8; 4] = b"SKYR";
+const FORMAT_VERSION: u16 = 3;
+const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
+// Bump whenever the same seed and inputs would play out differently (rng, spawning, movement,
+// collisions, ...). Old replays would silently desync otherwise, since the game version rarely moves.
+const SIMULATION_VERSION: u16 = 8;
+const FLAG_FIXED_STEP: u8 = 1;
+const FRAME_SIZE: usize = 9;
+
+thread_local! {
+    static LAST_RECORDING: RefCell<Option<Vec<u8>>> = RefCell::new(None);
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub delta_time: f64,
+    pub controls: Controls,
+}
+
+pub struct Recording {
+    config: RunConfig,
+    final_score: u128,
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Truncated,
+    // More bytes than the header says there are frames
+    TrailingBytes(usize),
+    BadMagic,
+    UnsupportedFormat(u16),
+    VersionMismatch { recorded: String, running: String },
+    SimulationMismatch { recorded: u16, running: u16 },
+    ChecksumMismatch,
+    UnknownMode(u8),
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordingError::Truncated => write!(f, "replay file is truncated"),
+            RecordingError::TrailingBytes(count) => write!(f, "replay file has {} unexpected bytes after the last frame", count),
+            RecordingError::BadMagic => write!(f, "not a Sky Combat replay file"),
+            RecordingError::UnsupportedFormat(version) => {
+                write!(f, "replay format version {} is not supported (expected {})", version, FORMAT_VERSION)
+            }
+            RecordingError::VersionMismatch { recorded, running } => {
+                write!(f, "replay was recorded with game version {}, but this is version {}", recorded, running)
+            }
+            RecordingError::SimulationMismatch { recorded, running } => {
+                write!(f, "replay was recorded with simulation version {}, but this game runs version {}", recorded, running)
+            }
+            RecordingError::ChecksumMismatch => write!(f, "replay file is corrupted (checksum mismatch)"),
+            RecordingError::UnknownMode(id) => write!(f, "replay uses unknown game mode {}", id),
+        }
+    }
+}
+
+impl Recording {
+    pub fn new(config: RunConfig) -> Recording {
+        Recording {
+            config,
+            final_score: 0,
+            frames: vec![],
+        }
+    }
+
+    pub fn push(&mut self, delta_time: f64, controls: Controls) {
+        self.frames.push(Frame { delta_time, controls });
+    }
+
+    pub fn finish(&mut self, final_score: u128) {
+        self.final_score = final_score;
+    }
+
+    pub fn config(&self) -> RunConfig {
+        self.config
+    }
+
+    pub fn final_score(&self) -> u128 {
+        self.final_score
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + self.frames.len() * FRAME_SIZE);
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.push(GAME_VERSION.len() as u8);
+        bytes.extend_from_slice(GAME_VERSION.as_bytes());
+        bytes.extend_from_slice(&SIMULATION_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.config.seed.to_le_bytes());
+        bytes.push(self.config.mode.to_id());
+        bytes.push(if self.config.fixed_step { FLAG_FIXED_STEP } else { 0 });
+        bytes.extend_from_slice(&self.final_score.to_le_bytes());
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+
+        for frame in &self.frames {
+            bytes.extend_from_slice(&frame.delta_time.to_bits().to_le_bytes());
+            bytes.push(frame.controls.to_bits());
+        }
+
+        let checksum = fnv1a(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Recording, RecordingError> {
+        if bytes.len() < MAGIC.len() + 4 {
+            return Err(RecordingError::Truncated);
+        }
+        if &bytes[..MAGIC.len()] != MAGIC {
+            return Err(RecordingError::BadMagic);
+        }
+
+        let (body, checksum) = bytes.split_at(bytes.len() - 4);
+        if fnv1a(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(RecordingError::ChecksumMismatch);
+        }
+
+        let mut reader = Reader { bytes: body, position: MAGIC.len() };
+
+        let format_version = u16::from_le_bytes(reader.take()?);
+        if format_version != FORMAT_VERSION {
+            return Err(RecordingError::UnsupportedFormat(format_version));
+        }
+
+        let version_length = reader.take::<1>()?[0] as usize;
+        let version = String::from_utf8_lossy(reader.take_slice(version_length)?).into_owned();
+        if version != GAME_VERSION {
+            return Err(RecordingError::VersionMismatch { recorded: version, running: String::from(GAME_VERSION) });
+        }
+
+        let simulation_version = u16::from_le_bytes(reader.take()?);
+        if simulation_version != SIMULATION_VERSION {
+            return Err(RecordingError::SimulationMismatch { recorded: simulation_version, running: SIMULATION_VERSION });
+        }
+
+        let seed = u64::from_le_bytes(reader.take()?);
+        let mode_id = reader.take::<1>()?[0];
+        let mode = Mode::from_id(mode_id).ok_or(RecordingError::UnknownMode(mode_id))?;
+        let flags = reader.take::<1>()?[0];
+        let final_score = u128::from_le_bytes(reader.take()?);
+        let frame_count = u32::from_le_bytes(reader.take()?) as usize;
+
+        let mut frames = Vec::with_capacity(frame_count.min(body.len() / FRAME_SIZE));
+        for _ in 0..frame_count {
+            let delta_time = f64::from_bits(u64::from_le_bytes(reader.take()?));
+            let controls = Controls::from_bits(reader.take::<1>()?[0]);
+            frames.push(Frame { delta_time, controls });
+        }
+        if reader.position != body.len() {
+            return Err(RecordingError::TrailingBytes(body.len() - reader.position));
+        }
+
+        Ok(Recording {
+            config: RunConfig {
+                seed,
+                mode,
+                fixed_step: flags & FLAG_FIXED_STEP != 0,
+            },
+            final_score,
+            frames,
+        })
+    }
+}
+
+// Keeps the bytes of the last finished run around so the page can offer them for download
+pub fn store_last(recording: &Recording) {
+    LAST_RECORDING.with(|last| *last.borrow_mut() = Some(recording.to_bytes()));
+}
+
+pub fn last() -> Option<Vec<u8>> {
+    LAST_RECORDING.with(|last| last.borrow().clone())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take_slice(&mut self, length: usize) -> Result<&'a [u8], RecordingError> {
+        let end = self.position + length;
+        if end > self.bytes.len() {
+            return Err(RecordingError::Truncated);
+        }
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], RecordingError> {
+        Ok(self.take_slice(N)?.try_into().unwrap())
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811C_9DC5;
+    for byte in bytes {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{World, FIXED_DELTA_TIME};
+
+    fn play(recording: &Recording) -> World {
+        let mut world = World::new(recording.config());
+        for frame in recording.frames() {
+            world.advance(&frame.controls, frame.delta_time);
+        }
+        world
+    }
+
+    #[test]
+    fn replay_round_trip_plays_out_the_same() {
+        let mut world = World::new(RunConfig::new(7));
+        let mut recording = Recording::new(world.config());
+        for i in 0..1200 {
+            // Weave left and right so the run isn't just standing still
+            let controls = Controls {
+                left: (i / 90) % 2 == 0,
+                right: (i / 90) % 2 == 1,
+                up: i % 200 < 40,
+                ..Controls::default()
+            };
+            world.advance(&controls, FIXED_DELTA_TIME);
+            recording.push(FIXED_DELTA_TIME, controls);
+        }
+        recording.finish(world.score());
+
+        let bytes = recording.to_bytes();
+        let loaded = Recording::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.final_score(), world.score());
+        assert_eq!(loaded.frames().len(), recording.frames().len());
+
+        let replayed = play(&loaded);
+        assert_eq!(replayed.score(), loaded.final_score());
+        assert_eq!(replayed.elapsed(), world.elapsed());
+
+        let reloaded = loaded.to_bytes();
+        assert_eq!(fnv1a(&reloaded[..reloaded.len() - 4]), fnv1a(&bytes[..bytes.len() - 4]));
+        assert_eq!(reloaded, bytes);
+    }
+
+    // Checksum over everything before it, for tests that tamper with the body
+    fn reseal(bytes: &mut [u8]) {
+        let length = bytes.len();
+        let checksum = fnv1a(&bytes[..length - 4]);
+        bytes[length - 4..].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    #[test]
+    fn other_simulation_version_is_rejected() {
+        let mut bytes = Recording::new(RunConfig::new(7)).to_bytes();
+        let offset = MAGIC.len() + 2 + 1 + GAME_VERSION.len();
+        bytes[offset..offset + 2].copy_from_slice(&(SIMULATION_VERSION + 1).to_le_bytes());
+        reseal(&mut bytes);
+
+        match Recording::from_bytes(&bytes) {
+            Err(RecordingError::SimulationMismatch { recorded, running }) => {
+                assert_eq!(recorded, SIMULATION_VERSION + 1);
+                assert_eq!(running, SIMULATION_VERSION);
+            }
+            _ => panic!("expected a simulation version mismatch"),
+        }
+    }
+
+    #[test]
+    fn only_an_exact_length_is_accepted() {
+        let mut recording = Recording::new(RunConfig::new(7));
+        recording.push(FIXED_DELTA_TIME, Controls::default());
+        let bytes = recording.to_bytes();
+
+        // Stray byte between the last frame and the checksum, checksum fixed up to match
+        let mut padded = bytes.clone();
+        padded.insert(bytes.len() - 4, 0);
+        reseal(&mut padded);
+        assert!(matches!(Recording::from_bytes(&padded), Err(RecordingError::TrailingBytes(1))));
+
+        let mut appended = bytes.clone();
+        appended.push(0);
+        assert!(Recording::from_bytes(&appended).is_err());
+
+        assert!(matches!(Recording::from_bytes(&bytes[..bytes.len() - 1]), Err(RecordingError::ChecksumMismatch)));
+        assert_eq!(Recording::from_bytes(&bytes).unwrap().frames().len(), 1);
+    }
+}
@@ -0,0 +1,214 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::scene::Scene;
+use flask::renderer::Renderer;
+use flask::game_status::GameStatus;
+use flask::input::Input;
+use flask::font::Font;
+use flask::palette::FlaskColor;
+
+use crate::controls::Controls;
+use crate::game::Game;
+use crate::recording::Recording;
+use crate::title::Title;
+use crate::world::World;
+
+const SPEEDS: [usize; 4] = [1, 2, 4, 8];
+// Frames between snapshots. Seeking re-simulates at most this many frames.
+const SNAPSHOT_INTERVAL: usize = 120;
+const SEEK_STEP: f64 = 5.0;
+
+// Plays a recorded run back through Game.
+//   Enter - pause/resume, W/S - playback speed,
+//   A/D - seek 5 seconds back/forward, or a single frame while paused, Escape - back to the title.
+// Speed-up steps several recorded frames per rendered frame instead of raising game_speed,
+// since feeding the simulation different delta times would change how the run plays out.
+pub struct Replay {
+    game: Game,
+    recording: Recording,
+    // Run time at the start of every frame, plus the total at the end
+    elapsed: Vec<f64>,
+    frame_index: usize,
+    snapshots: Vec<(usize, World)>,
+    speed_index: usize,
+    paused: bool,
+    previous_controls: Controls,
+    font: Font,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Replay {
+        let mut elapsed = Vec::with_capacity(recording.frames().len() + 1);
+        let mut total = 0.0;
+        elapsed.push(total);
+        for frame in recording.frames() {
+            total += frame.delta_time;
+            elapsed.push(total);
+        }
+
+        Replay {
+            game: Game::playback(recording.config()),
+            recording,
+            elapsed,
+            frame_index: 0,
+            snapshots: vec![],
+            speed_index: 0,
+            paused: false,
+            previous_controls: Controls::default(),
+            font: Font::load_3x5().unwrap(),
+        }
+    }
+
+    fn frame_count(&self) -> usize {
+        self.recording.frames().len()
+    }
+
+    fn finished(&self) -> bool {
+        self.frame_index >= self.frame_count() || self.game.world().is_over()
+    }
+
+    fn step_frame(&mut self) {
+        if self.frame_index >= self.frame_count() {
+            return;
+        }
+
+        let snapshot_due = self.frame_index % SNAPSHOT_INTERVAL == 0;
+        let already_taken = self.snapshots.last().map_or(false, |(frame, _)| *frame >= self.frame_index);
+        if snapshot_due && !already_taken {
+            self.snapshots.push((self.frame_index, self.game.world().clone()));
+        }
+
+        let frame = self.recording.frames()[self.frame_index];
+        self.game.step(&frame.controls, frame.delta_time);
+        self.frame_index += 1;
+    }
+
+    pub fn seek(&mut self, target: usize) {
+        let target = target.min(self.frame_count());
+
+        let snapshot = self.snapshots.iter().rev().find(|(frame, _)| *frame <= target);
+        if let Some((frame, world)) = snapshot {
+            // Rewinding always needs a snapshot, going forward only when it skips frames
+            if target < self.frame_index || *frame > self.frame_index {
+                self.frame_index = *frame;
+                self.game.restore(world.clone());
+            }
+        }
+
+        while self.frame_index < target {
+            self.step_frame();
+        }
+    }
+
+    pub fn seek_time(&mut self, seconds: f64) {
+        let frame = self.elapsed.partition_point(|time| *time <= seconds).saturating_sub(1);
+        self.seek(frame);
+    }
+
+    fn draw_hud(&self, renderer: &mut Renderer) {
+        let (window_w, window_h) = renderer.get_window_size();
+
+        let state_text = match self.paused {
+            true => String::from("PAUSED"),
+            false => String::from(format!("REPLAY {}X", SPEEDS[self.speed_index])),
+        };
+        renderer.text(&state_text, &self.font, (window_w / 2) - 5 - 4 * state_text.len() as i64, window_h - 10, FlaskColor::Red as u8);
+
+        let time_text = String::from(format!(
+            "{:.1}/{:.1}S  F {}/{}",
+            self.elapsed[self.frame_index],
+            self.elapsed[self.frame_count()],
+            self.frame_index,
+            self.frame_count()
+        ));
+        renderer.text(&time_text, &self.font, -(window_w / 2) + 5, 5, FlaskColor::Red as u8);
+
+        if self.finished() {
+            let score = self.game.world().score();
+            let result_text = match score == self.recording.final_score() {
+                true => String::from("REPLAY MATCHES"),
+                false => String::from(format!("REPLAY DIVERGED: {} VS {}", score, self.recording.final_score())),
+            };
+            renderer.text(&result_text, &self.font, -(4 * (result_text.len() as i64 / 2)), window_h / 2, FlaskColor::Red as u8);
+        }
+    }
+}
+
+impl Scene for Replay {
+    fn on_start(&mut self, renderer: &mut Renderer) {
+        self.game.load(renderer);
+    }
+
+    fn on_update(&mut self, _game_status: &mut GameStatus, renderer: &mut Renderer, input: &Input, _delta_time: f64) -> Option<Box<dyn Scene>> {
+        renderer.clear_screen();
+
+        let controls = Controls::from_input(input);
+        let previous = self.previous_controls;
+        self.previous_controls = controls;
+
+        if controls.escape {
+            return Some(Box::new(Title::new()));
+        }
+
+        if controls.enter && !previous.enter {
+            if self.finished() {
+                return Some(Box::new(Title::new()));
+            }
+            self.paused = !self.paused;
+        }
+        if controls.up && !previous.up && self.speed_index + 1 < SPEEDS.len() {
+            self.speed_index += 1;
+        }
+        if controls.down && !previous.down && self.speed_index > 0 {
+            self.speed_index -= 1;
+        }
+
+        if controls.right && !previous.right {
+            match self.paused {
+                true => self.seek(self.frame_index + 1),
+                false => self.seek_time(self.elapsed[self.frame_index] + SEEK_STEP),
+            }
+        }
+        if controls.left && !previous.left {
+            match self.paused {
+                true => self.seek(self.frame_index.saturating_sub(1)),
+                false => self.seek_time(self.elapsed[self.frame_index] - SEEK_STEP),
+            }
+        }
+
+        if !self.paused {
+            for _ in 0..SPEEDS[self.speed_index] {
+                if self.finished() {
+                    break;
+                }
+                self.step_frame();
+            }
+        }
+
+        self.game.draw(renderer);
+        self.draw_hud(renderer);
+
+        None
+    }
+
+    fn on_destroy(&mut self) {
+
+    }
+}
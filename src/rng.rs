@@ -0,0 +1,85 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+// Independent random streams derived from the run seed. Each consumer gets its own stream so that,
+// for example, drawing more clouds or longer engine flames never shifts where enemies spawn.
+// New kinds of randomness should get a new stream rather than borrow an existing one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stream {
+    Spawning = 1,
+    Scenery = 2,
+    Particles = 3,
+    Patterns = 4,
+    Drops = 5,
+}
+
+// Small xorshift64* generator. Unlike flask's Rand it can be cloned, which replay snapshots rely on,
+// and its sequence is pinned down here rather than by whatever flask happens to use.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new_with_seed(seed: u64) -> Rng {
+        // xorshift gets stuck on zero
+        Rng { state: mix(seed) | 1 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Uniform in [min, max)
+    pub fn next_i64_in_range(&mut self, min: i64, max: i64) -> i64 {
+        if max <= min {
+            return min;
+        }
+        let span = max.wrapping_sub(min) as u64;
+        min.wrapping_add((self.next_u64() % span) as i64)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u64() >> 63 == 1
+    }
+}
+
+pub fn stream_seed(seed: u64, stream: Stream) -> u64 {
+    mix(seed ^ (stream as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+pub fn stream_rng(seed: u64, stream: Stream) -> Rng {
+    Rng::new_with_seed(stream_seed(seed, stream))
+}
+
+// splitmix64 finalizer, so neighbouring seeds and streams end up far apart
+fn mix(value: u64) -> u64 {
+    let mut z = value;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,116 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use std::cell::Cell;
+
+use flask::renderer::Renderer;
+use flask::font::Font;
+use flask::palette::FlaskColor;
+
+use crate::controls::Controls;
+use crate::menu::{self, Menu};
+use crate::mode::Mode;
+use crate::overlay::{Action, Overlay};
+
+const FIXED_STEP: usize = 0;
+const SHOW_SEED: usize = 1;
+const BACK: usize = 2;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    // Applies to runs started afterwards, a run never changes stepping halfway through
+    pub fixed_step: bool,
+    pub show_seed: bool,
+    // Picked on the title screen
+    pub mode: Mode,
+}
+
+thread_local! {
+    static SETTINGS: Cell<Settings> = Cell::new(Settings {
+        fixed_step: true,
+        show_seed: false,
+        mode: Mode::Normal,
+    });
+}
+
+pub fn get() -> Settings {
+    SETTINGS.with(|settings| settings.get())
+}
+
+pub fn set(settings: Settings) {
+    SETTINGS.with(|current| current.set(settings));
+}
+
+pub struct SettingsMenu {
+    menu: Menu,
+}
+
+impl SettingsMenu {
+    pub fn new() -> SettingsMenu {
+        let mut settings_menu = SettingsMenu {
+            menu: Menu::new(&["", "", "BACK"]),
+        };
+        settings_menu.refresh();
+        settings_menu
+    }
+
+    fn refresh(&mut self) {
+        let settings = get();
+        self.menu.set_item(FIXED_STEP, String::from(format!("FIXED STEP: {}", on_off(settings.fixed_step))));
+        self.menu.set_item(SHOW_SEED, String::from(format!("SHOW SEED: {}", on_off(settings.show_seed))));
+    }
+}
+
+impl Overlay for SettingsMenu {
+    fn on_update(&mut self, pressed: &Controls) -> Action {
+        if pressed.escape {
+            return Action::Pop;
+        }
+
+        let mut settings = get();
+        match self.menu.on_update(pressed) {
+            Some(FIXED_STEP) => settings.fixed_step = !settings.fixed_step,
+            Some(SHOW_SEED) => settings.show_seed = !settings.show_seed,
+            Some(BACK) => return Action::Pop,
+            _ => return Action::Stay,
+        }
+        set(settings);
+        self.refresh();
+
+        Action::Stay
+    }
+
+    fn on_draw(&self, renderer: &mut Renderer, font: &Font) {
+        let (_, window_h) = renderer.get_window_size();
+        let top = window_h / 2 + 25;
+
+        menu::panel(renderer, -60, top - 15 - self.menu.height() - 5, 120, self.menu.height() + 20);
+
+        let title = String::from("SETTINGS");
+        renderer.text(&title, font, -(4 * (title.len() as i64 / 2)), top - 10, FlaskColor::Red as u8);
+        self.menu.on_draw(renderer, font, 0, top - 15);
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    match value {
+        true => "ON",
+        false => "OFF",
+    }
+}
@@ -0,0 +1,138 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use crate::vec2::Vec2;
+
+// Squadrons are a leader flying the wave's path with wingmen holding offsets from it.
+// When the leader is shot down the wingmen break formation and go for the player,
+// when it gets away they carry on along its path.
+
+// Score on top of the per enemy score, for every member, when the whole squadron is destroyed
+const WIPE_BONUS_PER_MEMBER: u128 = 100;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shape {
+    V,
+    LineAbreast,
+    // Diagonal line trailing back to the right
+    Echelon,
+    Column,
+}
+
+impl Shape {
+    pub const NAMES: [&'static str; 4] = ["v", "line_abreast", "echelon", "column"];
+
+    pub fn from_name(name: &str) -> Option<Shape> {
+        match name {
+            "v" => Some(Shape::V),
+            "line_abreast" => Some(Shape::LineAbreast),
+            "echelon" => Some(Shape::Echelon),
+            "column" => Some(Shape::Column),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Formation {
+    pub shape: Shape,
+    // Members including the leader
+    pub size: u32,
+    pub spacing: f64,
+}
+
+impl Formation {
+    pub const DEFAULT_SIZE: u32 = 5;
+    pub const DEFAULT_SPACING: f64 = 16.0;
+
+    pub fn new(shape: Shape) -> Formation {
+        Formation {
+            shape,
+            size: Formation::DEFAULT_SIZE,
+            spacing: Formation::DEFAULT_SPACING,
+        }
+    }
+
+    // Where each member sits relative to the leader, leader first. Enemies fly down the screen,
+    // so positive y is behind the leader.
+    pub fn offsets(&self) -> Vec<Vec2> {
+        let mut offsets = vec![];
+        for i in 0..self.size {
+            // Wingmen alternate sides, moving one rank out every pair
+            let rank = ((i + 1) / 2) as f64;
+            let side = if i % 2 == 1 { -1.0 } else { 1.0 };
+
+            let (x, y) = match self.shape {
+                Shape::V => (side * rank, rank),
+                Shape::LineAbreast => (side * rank, 0.0),
+                Shape::Echelon => (i as f64, i as f64),
+                Shape::Column => (0.0, i as f64),
+            };
+            offsets.push(Vec2::new(x, y) * self.spacing);
+        }
+        offsets
+    }
+}
+
+// Keeps count of the members still flying, to tell whether the player got all of them
+#[derive(Clone, Debug)]
+pub struct Squadron {
+    pub id: u32,
+    size: u32,
+    remaining: u32,
+    // Some member flew off screen, so no bonus
+    escaped: bool,
+    leader_destroyed: bool,
+}
+
+impl Squadron {
+    pub fn new(id: u32, size: u32) -> Squadron {
+        Squadron {
+            id,
+            size,
+            remaining: size,
+            escaped: false,
+            leader_destroyed: false,
+        }
+    }
+
+    // Returns the wipe bonus once the last member is gone, if none of them got away
+    pub fn remove_member(&mut self, destroyed: bool, leader: bool) -> Option<u128> {
+        self.remaining = self.remaining.saturating_sub(1);
+        if !destroyed {
+            self.escaped = true;
+        }
+        if leader && destroyed {
+            self.leader_destroyed = true;
+        }
+
+        match self.remaining == 0 && !self.escaped {
+            true => Some(WIPE_BONUS_PER_MEMBER * self.size as u128),
+            false => None,
+        }
+    }
+
+    pub fn leader_destroyed(&self) -> bool {
+        self.leader_destroyed
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+}
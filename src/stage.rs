@@ -0,0 +1,570 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use crate::aim::Aim;
+use crate::behaviour::Behaviour;
+use crate::boss::BossKind;
+use crate::enemy::EnemyKind;
+use crate::json::{self, Error, Value};
+use crate::path::{self, PathLibrary};
+use crate::pattern::Pattern;
+use crate::squadron::{Formation, Shape};
+use crate::world::WALK_AREA_MAX_X;
+
+// Stages are plain data, see assets/stages.json. Each wave sends `count` enemies of one kind,
+// the first `time` seconds into the stage and then one every `interval` seconds.
+// A stage with a boss sends it once every wave is spawned and shot down or gone.
+const STAGES: &str = include_str!("../assets/stages.json");
+// Seconds the warning banner shows before a boss flies in
+pub const BOSS_WARNING_TIME: f64 = 3.0;
+// Seconds of "STAGE CLEAR" after a boss goes down
+pub const STAGE_CLEAR_TIME: f64 = 3.0;
+
+#[derive(Clone, Debug)]
+pub struct Stage {
+    pub name: String,
+    pub waves: Vec<Wave>,
+    pub boss: Option<BossKind>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Wave {
+    pub time: f64,
+    pub enemy: EnemyKind,
+    pub count: u32,
+    pub interval: f64,
+    // None enters at a random position
+    pub x: Option<f64>,
+    // None drifts toward the middle of the screen from wherever it entered
+    pub path: Option<Behaviour>,
+    // None doesn't shoot
+    pub fire: Option<Pattern>,
+    pub aim: Aim,
+    // Each enemy sent is the leader of a whole squadron
+    pub formation: Option<Formation>,
+}
+
+// Stages compiled into the game, with errors saying which file they're in
+pub fn builtin() -> Result<Vec<Stage>, String> {
+    let paths = path::builtin().map_err(|error| String::from(format!("assets/paths.json {}", error)))?;
+    load(STAGES, &paths).map_err(|error| String::from(format!("assets/stages.json {}", error)))
+}
+
+pub fn load(text: &str, paths: &PathLibrary) -> Result<Vec<Stage>, Error> {
+    let document = json::parse(text)?;
+    document.check_fields(&["stages"])?;
+
+    let stages_value = document.field("stages")?;
+    let mut stages = vec![];
+    for stage_value in stages_value.as_array()? {
+        stages.push(load_stage(stage_value, paths)?);
+    }
+
+    if stages.is_empty() {
+        return Err(Error::new(stages_value.line, "at least one stage is required"));
+    }
+
+    Ok(stages)
+}
+
+fn load_stage(value: &Value, paths: &PathLibrary) -> Result<Stage, Error> {
+    value.check_fields(&["name", "waves", "boss"])?;
+
+    let waves_value = value.field("waves")?;
+    let mut waves = vec![];
+    for wave_value in waves_value.as_array()? {
+        waves.push(load_wave(wave_value, paths)?);
+    }
+
+    if waves.is_empty() {
+        return Err(Error::new(waves_value.line, "stage needs at least one wave"));
+    }
+
+    let boss = match value.get("boss")? {
+        Some(boss_value) => {
+            let name = boss_value.as_str()?;
+            Some(lookup(boss_value, name, BossKind::from_name(name), &BossKind::NAMES)?)
+        }
+        None => None,
+    };
+
+    Ok(Stage {
+        name: String::from(value.field("name")?.as_str()?),
+        waves,
+        boss,
+    })
+}
+
+fn load_wave(value: &Value, paths: &PathLibrary) -> Result<Wave, Error> {
+    value.check_fields(&["time", "enemy", "count", "interval", "x", "path", "fire", "aim", "formation"])?;
+
+    let time_value = value.field("time")?;
+    let time = time_value.as_f64()?;
+    if time < 0.0 {
+        return Err(Error::new(time_value.line, "time can't be negative"));
+    }
+
+    let count_value = value.field("count")?;
+    let count = count_value.as_u32()?;
+    if count == 0 {
+        return Err(Error::new(count_value.line, "count must be at least 1"));
+    }
+
+    let interval = match value.get("interval")? {
+        Some(interval_value) => {
+            let interval = interval_value.as_f64()?;
+            if interval < 0.0 {
+                return Err(Error::new(interval_value.line, "interval can't be negative"));
+            }
+            interval
+        }
+        None => 0.0,
+    };
+
+    let x = match value.get("x")? {
+        Some(x_value) => load_x(x_value)?,
+        None => None,
+    };
+
+    let path = match value.get("path")? {
+        Some(path_value) => load_path(path_value, paths)?,
+        None => None,
+    };
+
+    let formation = match value.get("formation")? {
+        Some(formation_value) => Some(load_formation(formation_value)?),
+        None => None,
+    };
+
+    let enemy_value = value.field("enemy")?;
+    let enemy_name = enemy_value.as_str()?;
+    let enemy = lookup(enemy_value, enemy_name, EnemyKind::from_name(enemy_name), &EnemyKind::NAMES)?;
+
+    let fire = match value.get("fire")? {
+        Some(fire_value) => load_fire(fire_value)?,
+        None => Some(enemy.pattern()),
+    };
+
+    let aim = match value.get("aim")? {
+        Some(aim_value) => load_aim(aim_value, enemy)?,
+        None => Aim::PLAIN,
+    };
+
+    Ok(Wave {
+        time,
+        enemy,
+        count,
+        interval,
+        x,
+        path,
+        fire,
+        aim,
+        formation,
+    })
+}
+
+fn load_x(value: &Value) -> Result<Option<f64>, Error> {
+    if let Ok("random") = value.as_str() {
+        return Ok(None);
+    }
+
+    let x = value.as_f64().map_err(|_| Error::new(value.line, "x must be a number or \"random\""))?;
+    if x.abs() > WALK_AREA_MAX_X {
+        return Err(Error::new(value.line, format!("x {} is off screen, keep it within -{} to {}", x, WALK_AREA_MAX_X, WALK_AREA_MAX_X)));
+    }
+
+    Ok(Some(x))
+}
+
+// Either a behaviour name, or an object with "type" and any parameters to override.
+// Authored paths are { "type": "path", "name": "...", "mirrored": true/false }.
+fn load_path(value: &Value, paths: &PathLibrary) -> Result<Option<Behaviour>, Error> {
+    let (type_value, parameters) = match value.as_str() {
+        Ok(_) => (value, None),
+        Err(_) => (value.field("type")?, Some(value)),
+    };
+
+    let name = type_value.as_str()?;
+    if name == "auto" {
+        return Ok(None);
+    }
+    if name == "path" {
+        return load_follow_path(value, paths).map(Some);
+    }
+    let mut behaviour = lookup(type_value, name, Behaviour::from_name(name), &Behaviour::NAMES)?;
+
+    if let Some(parameters) = parameters {
+        let mut known = vec!["type"];
+        known.extend_from_slice(behaviour.parameter_names());
+        parameters.check_fields(&known)?;
+
+        for (parameter, parameter_value) in parameters.as_object()? {
+            if parameter == "type" {
+                continue;
+            }
+            let number = parameter_value.as_f64()?;
+            // A zero period divides by zero and leaves the enemy stuck, negative times make no sense either
+            if (parameter == "period" || parameter == "duration") && number <= 0.0 {
+                return Err(Error::new(parameter_value.line, format!("{} must be greater than 0", parameter)));
+            }
+            behaviour.set_parameter(parameter, number);
+        }
+    }
+
+    Ok(Some(behaviour))
+}
+
+fn load_follow_path(value: &Value, paths: &PathLibrary) -> Result<Behaviour, Error> {
+    value.check_fields(&["type", "name", "mirrored"])?;
+
+    let name_value = value.field("name")?;
+    let name = name_value.as_str()?;
+    let path = lookup(name_value, name, paths.get(name), &paths.names())?;
+
+    let mirrored = match value.get("mirrored")? {
+        Some(mirrored) => mirrored.as_bool()?,
+        None => false,
+    };
+
+    Ok(Behaviour::FollowPath { path, mirrored })
+}
+
+// "plain" shoots straight at the player, "lead" uses the enemy kind's own gunnery,
+// or an object with "lead" (0 to 1) and "error" (degrees) sets it directly.
+fn load_aim(value: &Value, enemy: EnemyKind) -> Result<Aim, Error> {
+    match value.as_str() {
+        Ok("plain") => return Ok(Aim::PLAIN),
+        Ok("lead") => return Ok(enemy.aim()),
+        Ok(name) => return Err(Error::new(value.line, format!("unknown aim \"{}\", expected \"plain\", \"lead\" or an object", name))),
+        Err(_) => {}
+    }
+
+    value.check_fields(&["lead", "error"])?;
+    let mut aim = Aim::PLAIN;
+    if let Some(lead_value) = value.get("lead")? {
+        aim.lead = lead_value.as_f64()?;
+        if !(0.0..=1.0).contains(&aim.lead) {
+            return Err(Error::new(lead_value.line, "lead must be between 0 and 1"));
+        }
+    }
+    if let Some(error_value) = value.get("error")? {
+        aim.error = error_value.as_f64()?;
+        if aim.error < 0.0 {
+            return Err(Error::new(error_value.line, "error can't be negative"));
+        }
+    }
+
+    Ok(aim)
+}
+
+// Either a pattern name, "none", or an object with "type" and any parameters to override.
+// Bursts take the pattern they repeat as "pattern", combos a list of them as "patterns".
+fn load_fire(value: &Value) -> Result<Option<Pattern>, Error> {
+    if let Ok("none") = value.as_str() {
+        return Ok(None);
+    }
+    load_pattern(value).map(Some)
+}
+
+fn load_pattern(value: &Value) -> Result<Pattern, Error> {
+    let (type_value, parameters) = match value.as_str() {
+        Ok(_) => (value, None),
+        Err(_) => (value.field("type")?, Some(value)),
+    };
+
+    let name = type_value.as_str()?;
+    let mut pattern = lookup(type_value, name, Pattern::from_name(name), &Pattern::NAMES)?;
+
+    if let Some(parameters) = parameters {
+        let mut known = vec!["type"];
+        known.extend_from_slice(pattern.parameter_names());
+        match pattern {
+            Pattern::Burst { .. } => known.push("pattern"),
+            Pattern::Combo(_) => known.push("patterns"),
+            _ => {}
+        }
+        parameters.check_fields(&known)?;
+
+        for (parameter, parameter_value) in parameters.as_object()? {
+            match parameter.as_str() {
+                "type" => {}
+                "pattern" => {
+                    if let Pattern::Burst { pattern: inner, .. } = &mut pattern {
+                        *inner = Box::new(load_pattern(parameter_value)?);
+                    }
+                }
+                "patterns" => {
+                    if let Pattern::Combo(patterns) = &mut pattern {
+                        for pattern_value in parameter_value.as_array()? {
+                            patterns.push(load_pattern(pattern_value)?);
+                        }
+                    }
+                }
+                "count" => {
+                    let count = parameter_value.as_u32()?;
+                    if count == 0 {
+                        return Err(Error::new(parameter_value.line, "count must be at least 1"));
+                    }
+                    pattern.set_parameter(parameter, count as f64);
+                }
+                _ => {
+                    pattern.set_parameter(parameter, parameter_value.as_f64()?);
+                }
+            }
+        }
+    }
+
+    if let Pattern::Combo(patterns) = &pattern {
+        if patterns.is_empty() {
+            return Err(Error::new(value.line, "combo needs at least one pattern in \"patterns\""));
+        }
+    }
+
+    Ok(pattern)
+}
+
+// Either a shape name, or an object with "type" and optionally "size" and "spacing"
+fn load_formation(value: &Value) -> Result<Formation, Error> {
+    let type_value = match value.as_str() {
+        Ok(_) => value,
+        Err(_) => {
+            value.check_fields(&["type", "size", "spacing"])?;
+            value.field("type")?
+        }
+    };
+
+    let name = type_value.as_str()?;
+    let mut formation = Formation::new(lookup(type_value, name, Shape::from_name(name), &Shape::NAMES)?);
+    if value.as_str().is_ok() {
+        return Ok(formation);
+    }
+
+    if let Some(size_value) = value.get("size")? {
+        formation.size = size_value.as_u32()?;
+        if formation.size == 0 {
+            return Err(Error::new(size_value.line, "formation size must be at least 1"));
+        }
+    }
+    if let Some(spacing_value) = value.get("spacing")? {
+        formation.spacing = spacing_value.as_f64()?;
+    }
+
+    Ok(formation)
+}
+
+fn lookup<T>(value: &Value, name: &str, found: Option<T>, names: &[&str]) -> Result<T, Error> {
+    found.ok_or_else(|| Error::new(value.line, format!("unknown name \"{}\", expected one of: {}", name, names.join(", "))))
+}
+
+// Where the current stage is at
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StagePhase {
+    Waves,
+    // Warning banner is up, the boss flies in once it is done
+    BossWarning,
+    Boss,
+    Clear,
+}
+
+// Walks through the stages, telling the world which enemies are due. Stages repeat once the last one is cleared.
+#[derive(Clone)]
+pub struct StageRunner {
+    stages: Vec<Stage>,
+    stage_index: usize,
+    stage_time: f64,
+    // Enemies already sent for each wave of the current stage
+    spawned: Vec<u32>,
+    phase: StagePhase,
+    phase_time: f64,
+    // Boss is due but the world hasn't picked it up yet
+    boss_due: bool,
+    stages_cleared: u32,
+}
+
+impl StageRunner {
+    pub fn new(stages: Vec<Stage>) -> StageRunner {
+        let spawned = vec![0; stages[0].waves.len()];
+        StageRunner {
+            stages,
+            stage_index: 0,
+            stage_time: 0.0,
+            spawned,
+            phase: StagePhase::Waves,
+            phase_time: 0.0,
+            boss_due: false,
+            stages_cleared: 0,
+        }
+    }
+
+    pub fn stage(&self) -> &Stage {
+        &self.stages[self.stage_index]
+    }
+
+    pub fn stage_time(&self) -> f64 {
+        self.stage_time
+    }
+
+    // Counts every stage finished, going on past the last one as stages repeat
+    pub fn stages_cleared(&self) -> u32 {
+        self.stages_cleared
+    }
+
+    pub fn phase(&self) -> StagePhase {
+        self.phase
+    }
+
+    // Seconds since the current phase began
+    pub fn phase_time(&self) -> f64 {
+        self.phase_time
+    }
+
+    fn all_spawned(&self) -> bool {
+        self.stage().waves.iter().zip(&self.spawned).all(|(wave, spawned)| *spawned >= wave.count)
+    }
+
+    fn enter(&mut self, phase: StagePhase) {
+        self.phase = phase;
+        self.phase_time = 0.0;
+    }
+
+    fn next_stage(&mut self) {
+        self.stages_cleared += 1;
+        self.stage_index = (self.stage_index + 1) % self.stages.len();
+        self.stage_time = 0.0;
+        self.spawned = vec![0; self.stage().waves.len()];
+        self.enter(StagePhase::Waves);
+    }
+
+    // Advances the stage clock and returns waves that have an enemy due, once per enemy.
+    // Step is in stage seconds, which are real seconds at Normal speed and shorter in faster modes.
+    pub fn update(&mut self, delta_time: f64, enemies_alive: usize, boss_alive: bool) -> Vec<Wave> {
+        match self.phase {
+            StagePhase::Waves => {
+                if self.all_spawned() && enemies_alive == 0 {
+                    match self.stage().boss {
+                        Some(_) => self.enter(StagePhase::BossWarning),
+                        None => self.next_stage(),
+                    }
+                }
+            }
+            StagePhase::BossWarning => {
+                if self.phase_time >= BOSS_WARNING_TIME {
+                    self.enter(StagePhase::Boss);
+                    self.boss_due = true;
+                }
+            }
+            StagePhase::Boss => {
+                if !boss_alive && !self.boss_due {
+                    self.enter(StagePhase::Clear);
+                }
+            }
+            StagePhase::Clear => {
+                if self.phase_time >= STAGE_CLEAR_TIME {
+                    self.next_stage();
+                }
+            }
+        }
+
+        self.stage_time += delta_time;
+        self.phase_time += delta_time;
+
+        let mut due = vec![];
+        for (wave, spawned) in self.stages[self.stage_index].waves.iter().zip(self.spawned.iter_mut()) {
+            while *spawned < wave.count && self.stage_time >= wave.time + wave.interval * *spawned as f64 {
+                due.push(wave.clone());
+                *spawned += 1;
+            }
+        }
+
+        due
+    }
+
+    // Boss that should fly in now, handed out once
+    pub fn take_boss(&mut self) -> Option<BossKind> {
+        if !self.boss_due {
+            return None;
+        }
+        self.boss_due = false;
+        self.stage().boss
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One fighter wave with `fields` on line 6
+    fn stage_with(fields: &str) -> String {
+        format!(
+            "{{\n  \"stages\": [{{\n    \"name\": \"TEST\",\n    \"waves\": [\n      {{ \"time\": 1.0, \"enemy\": \"fighter\", \"count\": 1\n        {} }}\n    ]\n  }}]\n}}",
+            fields
+        )
+    }
+
+    fn stage_with_path(path: &str) -> String {
+        stage_with(&format!(", \"path\": {}", path))
+    }
+
+    // Shipped data is only loaded once the game runs, so a broken file has to fail here
+    #[test]
+    fn builtin_stages_load() {
+        if let Err(error) = builtin() {
+            panic!("{}", error);
+        }
+    }
+
+    #[test]
+    fn path_parameters_are_loaded() {
+        let stages = load(&stage_with_path("{ \"type\": \"zigzag\", \"width\": 12, \"period\": 5 }"), &path::builtin().unwrap()).unwrap();
+        assert_eq!(stages[0].waves[0].path, Some(Behaviour::ZigZag { width: 12.0, period: 5.0 }));
+    }
+
+    #[test]
+    fn non_positive_period_and_duration_are_rejected() {
+        for path in [
+            "{ \"type\": \"sine\", \"period\": 0 }",
+            "{ \"type\": \"zigzag\", \"period\": -2 }",
+            "{ \"type\": \"hover_strafe\", \"duration\": 0 }",
+        ] {
+            let error = load(&stage_with_path(path), &path::builtin().unwrap()).err().unwrap();
+            assert_eq!(error.line, 6, "{}", path);
+            assert!(error.message.contains("must be greater than 0"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn aim_defaults_to_plain() {
+        let stages = load(&stage_with(""), &path::builtin().unwrap()).unwrap();
+        assert_eq!(stages[0].waves[0].aim, Aim::PLAIN);
+
+        let stages = load(&stage_with(", \"aim\": \"lead\""), &path::builtin().unwrap()).unwrap();
+        assert_eq!(stages[0].waves[0].aim, EnemyKind::Fighter.aim());
+
+        let stages = load(&stage_with(", \"aim\": { \"lead\": 0.5, \"error\": 2 }"), &path::builtin().unwrap()).unwrap();
+        assert_eq!(stages[0].waves[0].aim, Aim { lead: 0.5, error: 2.0 });
+    }
+
+    #[test]
+    fn bad_aim_is_rejected() {
+        for aim in ["\"sniper\"", "{ \"lead\": 1.5 }", "{ \"error\": -1 }", "{ \"speed\": 1 }"] {
+            let error = load(&stage_with(&format!(", \"aim\": {}", aim)), &path::builtin().unwrap()).err().unwrap();
+            assert_eq!(error.line, 6, "{}", aim);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+// Small key-value persistence. Browser builds keep data in localStorage, native builds
+// (tools, tests) use files or plain memory.
+pub trait Storage {
+    fn load(&self, key: &str) -> Option<String>;
+
+    fn save(&mut self, key: &str, value: &str);
+}
+
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStorage {
+    values: std::collections::HashMap<String, String>,
+}
+
+#[cfg(test)]
+impl Storage for MemoryStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        self.values.insert(String::from(key), String::from(value));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    directory: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(directory: impl Into<std::path::PathBuf>) -> FileStorage {
+        FileStorage {
+            directory: directory.into(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.directory.join(key)).ok()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        if let Err(error) = std::fs::write(self.directory.join(key), value) {
+            flask::log(format!("Failed to save {}: {}", key, error).as_str());
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = localStorage, js_name = getItem)]
+        pub fn get_item(key: &str) -> Option<String>;
+
+        #[wasm_bindgen(js_namespace = localStorage, js_name = setItem)]
+        pub fn set_item(key: &str, value: &str);
+
+        #[wasm_bindgen(js_namespace = Date, js_name = now)]
+        pub fn date_now() -> f64;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorage;
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for LocalStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        browser::get_item(key)
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        browser::set_item(key, value);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn default_storage() -> Box<dyn Storage> {
+    Box::new(LocalStorage)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_storage() -> Box<dyn Storage> {
+    Box::new(FileStorage::new("."))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn unix_time() -> u64 {
+    (browser::date_now() / 1000.0) as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
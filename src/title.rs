@@ -0,0 +1,189 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::scene::Scene;
+use flask::renderer::Renderer;
+use flask::game_status::GameStatus;
+use flask::input::Input;
+use flask::font::Font;
+use flask::palette::FlaskColor;
+
+use crate::controls::Controls;
+use crate::game::Game;
+use crate::high_scores::HighScoresView;
+use crate::menu::{self, Menu};
+use crate::overlay::{Action, Overlay, OverlayStack};
+use crate::settings::{self, SettingsMenu};
+use crate::world::RunConfig;
+
+const START: usize = 0;
+const MODE: usize = 1;
+const HIGH_SCORES: usize = 2;
+const SETTINGS: usize = 3;
+const CREDITS: usize = 4;
+
+const CREDITS_TEXT: [&str; 4] = [
+    "SKY COMBAT",
+    "BY EGIDIJUS LILEIKA",
+    "",
+    "MADE WITH FLASK",
+];
+
+pub struct Title {
+    font: Font,
+    menu: Menu,
+    overlays: OverlayStack,
+    previous_controls: Controls,
+}
+
+impl Title {
+    pub fn new() -> Title {
+        let mut title = Title {
+            font: Font::load_3x5().unwrap(),
+            menu: Menu::new(&["START", "", "HIGH SCORES", "SETTINGS", "CREDITS"]),
+            overlays: OverlayStack::new(),
+            // Keys still held from the previous scene shouldn't count as presses
+            previous_controls: Controls::from_bits(u8::MAX),
+        };
+        title.refresh();
+        title
+    }
+
+    fn refresh(&mut self) {
+        self.menu.set_item(MODE, String::from(format!("MODE: {}", settings::get().mode.name())));
+    }
+
+    // Everything the frame's keys do, kept apart from on_update so it runs without a renderer
+    fn handle(&mut self, controls: Controls) -> Outcome {
+        let pressed = controls.pressed(&self.previous_controls);
+        self.previous_controls = controls;
+
+        if !self.overlays.is_empty() {
+            return match self.overlays.on_update(&pressed) {
+                Some(scene) => Outcome::Switch(scene),
+                None => Outcome::Stay,
+            };
+        }
+
+        if pressed.escape {
+            return Outcome::Quit;
+        }
+
+        match self.menu.on_update(&pressed) {
+            Some(START) => return Outcome::Start,
+            Some(MODE) => {
+                let mut current = settings::get();
+                current.mode = current.mode.next();
+                settings::set(current);
+                self.refresh();
+            }
+            Some(HIGH_SCORES) => self.overlays.push(Box::new(HighScoresView::new())),
+            Some(SETTINGS) => self.overlays.push(Box::new(SettingsMenu::new())),
+            Some(CREDITS) => self.overlays.push(Box::new(Credits)),
+            _ => {}
+        }
+        Outcome::Stay
+    }
+}
+
+enum Outcome {
+    Stay,
+    Quit,
+    Start,
+    Switch(Box<dyn Scene>),
+}
+
+impl Scene for Title {
+    fn on_start(&mut self, renderer: &mut Renderer) {
+        renderer.set_background_color(FlaskColor::Teal as u8).unwrap();
+        renderer.set_camera_y(renderer.get_window_size().1 / 2);
+    }
+
+    fn on_update(&mut self, game_status: &mut GameStatus, renderer: &mut Renderer, input: &Input, _delta_time: f64) -> Option<Box<dyn Scene>> {
+        renderer.clear_screen();
+
+        let next_scene: Option<Box<dyn Scene>> = match self.handle(Controls::from_input(input)) {
+            Outcome::Stay => None,
+            Outcome::Quit => {
+                game_status.quit();
+                return None;
+            }
+            Outcome::Start => Some(Box::new(Game::new(RunConfig::random()))),
+            Outcome::Switch(scene) => Some(scene),
+        };
+
+        let (_, window_h) = renderer.get_window_size();
+        let top = window_h / 2 + 50;
+
+        let title = String::from("SKY COMBAT");
+        renderer.text(&title, &self.font, -(4 * (title.len() as i64 / 2)), top, FlaskColor::Red as u8);
+        self.menu.on_draw(renderer, &self.font, 0, top - 20);
+
+        self.overlays.on_draw(renderer, &self.font);
+
+        next_scene
+    }
+
+    fn on_destroy(&mut self) {
+
+    }
+}
+
+struct Credits;
+
+impl Overlay for Credits {
+    fn on_update(&mut self, pressed: &Controls) -> Action {
+        match pressed.escape || pressed.enter {
+            true => Action::Pop,
+            false => Action::Stay,
+        }
+    }
+
+    fn on_draw(&self, renderer: &mut Renderer, font: &Font) {
+        let (_, window_h) = renderer.get_window_size();
+        let top = window_h / 2 + 30;
+
+        menu::panel(renderer, -50, top - 20 - 10 * CREDITS_TEXT.len() as i64, 100, 20 + 10 * CREDITS_TEXT.len() as i64);
+
+        for (i, line) in CREDITS_TEXT.iter().enumerate() {
+            let text = String::from(*line);
+            renderer.text(&text, font, -(4 * (text.len() as i64 / 2)), top - 10 - 10 * (i as i64 + 1), FlaskColor::Purple as u8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enter() -> Controls {
+        Controls { enter: true, ..Controls::default() }
+    }
+
+    #[test]
+    fn enter_held_from_previous_scene_does_not_start_a_run() {
+        let mut title = Title::new();
+
+        assert!(matches!(title.handle(enter()), Outcome::Stay));
+        assert!(matches!(title.handle(enter()), Outcome::Stay));
+
+        assert!(matches!(title.handle(Controls::default()), Outcome::Stay));
+        assert!(matches!(title.handle(enter()), Outcome::Start));
+    }
+}
@@ -0,0 +1,144 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+// Point or direction in pixels. Angles are in degrees, counter-clockwise from +x like everywhere else.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+    pub const fn new(x: f64, y: f64) -> Vec2 {
+        Vec2 { x, y }
+    }
+
+    // Unit vector pointing at `degrees`
+    pub fn from_angle(degrees: f64) -> Vec2 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Vec2::new(cos, sin)
+    }
+
+    pub fn dot(self, other: Vec2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    // Positive when `other` is counter-clockwise from this
+    pub fn cross(self, other: Vec2) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length_squared(self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(self, other: Vec2) -> f64 {
+        (other - self).length()
+    }
+
+    // Same direction at length 1, zero stays zero
+    pub fn normalised(self) -> Vec2 {
+        let length = self.length();
+        match length == 0.0 {
+            true => Vec2::ZERO,
+            false => self * (1.0 / length),
+        }
+    }
+
+    pub fn rotate(self, degrees: f64) -> Vec2 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Vec2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    // Same length, a quarter turn counter-clockwise
+    pub fn perpendicular(self) -> Vec2 {
+        Vec2::new(-self.y, self.x)
+    }
+
+    // Direction in degrees
+    pub fn angle(self) -> f64 {
+        self.y.atan2(self.x).to_degrees()
+    }
+
+    // Signed degrees to turn from this direction to `other`, -180 to 180
+    pub fn angle_to(self, other: Vec2) -> f64 {
+        self.cross(other).atan2(self.dot(other)).to_degrees()
+    }
+
+    pub fn lerp(self, other: Vec2, t: f64) -> Vec2 {
+        self + (other - self) * t
+    }
+
+    // Whole pixel coordinates, only for drawing
+    pub fn rounded(self) -> (i64, i64) {
+        (self.x.round() as i64, self.y.round() as i64)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, factor: f64) -> Vec2 {
+        Vec2::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        *self = *self - other;
+    }
+}
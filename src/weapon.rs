@@ -0,0 +1,148 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::palette::FlaskColor;
+
+use crate::homing::Homing;
+use crate::projectile::Projectile;
+use crate::vec2::Vec2;
+
+const MAX_LEVEL: u8 = 5;
+const UP: f64 = 90.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WeaponKind {
+    // Parallel guns either side of the nose
+    Twin,
+    // Fan of shots
+    Spread,
+    // Beams that go through whatever they hit
+    Laser,
+    // Slow missiles that steer into enemies
+    Homing,
+}
+
+impl WeaponKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeaponKind::Twin => "TWIN",
+            WeaponKind::Spread => "SPREAD",
+            WeaponKind::Laser => "LASER",
+            WeaponKind::Homing => "HOMING",
+        }
+    }
+
+    // Time between volleys at level 1
+    fn interval(&self) -> f64 {
+        match self {
+            WeaponKind::Twin => 1.0,
+            WeaponKind::Spread => 1.4,
+            WeaponKind::Laser => 0.6,
+            WeaponKind::Homing => 2.5,
+        }
+    }
+}
+
+// Player's gun. Knows what it fires at each level, the world just launches whatever comes out.
+#[derive(Clone, Debug)]
+pub struct Weapon {
+    kind: WeaponKind,
+    level: u8,
+    cooldown: f64,
+}
+
+impl Weapon {
+    pub fn new(kind: WeaponKind) -> Weapon {
+        Weapon {
+            kind,
+            level: 1,
+            cooldown: kind.interval(),
+        }
+    }
+
+    pub fn kind(&self) -> WeaponKind {
+        self.kind
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn level_up(&mut self) {
+        self.level = (self.level + 1).min(MAX_LEVEL);
+    }
+
+    // Swaps to another kind of weapon, keeping the level
+    pub fn switch(&mut self, kind: WeaponKind) {
+        self.kind = kind;
+        self.cooldown = self.cooldown.min(kind.interval());
+    }
+
+    pub fn update(&mut self, step: f64) {
+        self.cooldown -= step;
+    }
+
+    // Projectiles of a volley from `origin`, if the weapon is ready
+    pub fn fire(&mut self, origin: Vec2) -> Vec<Projectile> {
+        if self.cooldown >= 0.0 {
+            return vec![];
+        }
+        // Every level past the first fires 15% faster
+        self.cooldown = self.kind.interval() / (1.0 + 0.15 * (self.level - 1) as f64);
+
+        let level = self.level as i64;
+        let mut projectiles = vec![];
+        match self.kind {
+            WeaponKind::Twin => {
+                // Another pair of guns further out every other level
+                for pair in 0..(level + 1) / 2 {
+                    let offset = Vec2::new((10 + pair * 6) as f64, 0.0);
+                    projectiles.push(Projectile::new(Vec2::from_angle(UP) * 30.0, origin - offset, FlaskColor::Yellow as u8));
+                    projectiles.push(Projectile::new(Vec2::from_angle(UP) * 30.0, origin + offset, FlaskColor::Yellow as u8));
+                }
+            }
+            WeaponKind::Spread => {
+                let count = 1 + 2 * level;
+                let arc = 10.0 * level as f64;
+                for i in 0..count {
+                    let angle = UP - arc / 2.0 + arc * i as f64 / (count - 1) as f64;
+                    projectiles.push(Projectile::new(Vec2::from_angle(angle) * 25.0, origin, FlaskColor::Yellow as u8));
+                }
+            }
+            WeaponKind::Laser => {
+                let damage = 1 + (level / 3) as u8;
+                let offsets: &[f64] = if level < 3 { &[0.0] } else { &[-4.0, 4.0] };
+                for offset in offsets {
+                    let position = origin + Vec2::new(*offset, 6.0);
+                    projectiles.push(Projectile::new(Vec2::from_angle(UP) * 40.0, position, FlaskColor::White as u8).with_damage(damage).piercing());
+                }
+            }
+            WeaponKind::Homing => {
+                // Launched sideways, then they turn toward their target
+                let count = 1 + level / 2;
+                for i in 0..count {
+                    let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+                    let angle = UP + side * (30.0 + 15.0 * (i / 2) as f64);
+                    projectiles.push(Projectile::new(Vec2::from_angle(angle) * 15.0, origin, FlaskColor::Red as u8).with_damage(2).homing(Homing::player()));
+                }
+            }
+        }
+        projectiles
+    }
+}
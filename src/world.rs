@@ -0,0 +1,822 @@
+//
+// Copyright © 2021-2022  Egidijus Lileika
+//
+// This file is part of Sky Combat - Sample game of Flask game framework
+//
+// Sky Combat is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Sky Combat is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Sky Combat. If not, see <https://www.gnu.org/licenses/>.
+//
+
+use flask::rand::Rand;
+use flask::palette::FlaskColor;
+
+use crate::controls::Controls;
+use crate::player::{Player, HIT_FEEDBACK};
+use crate::entity::Entity;
+use crate::aim::Target;
+use crate::behaviour::Behaviour;
+use crate::blast::{Blast, BLAST_RADIUS};
+use crate::boss::{Boss, BossEvent};
+use crate::enemy::Enemy;
+use crate::projectile::Projectile;
+use crate::explosion::Explosion;
+use crate::homing::Homing;
+use crate::cloud::Cloud;
+use crate::collision::{Collisions, Handle, Layer, Mask};
+use crate::mode::Mode;
+use crate::pickup::{Pickup, PickupKind};
+use crate::rng::{stream_rng, Rng, Stream};
+use crate::settings;
+use crate::squadron::Squadron;
+use crate::stage::{self, StageRunner};
+use crate::vec2::Vec2;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+pub const WALK_AREA_MAX_X: f64 = (WINDOW_WIDTH / 2) as f64;
+pub const WALK_AREA_MAX_Y: f64 = WINDOW_HEIGHT as f64;
+pub const FIXED_DELTA_TIME: f64 = 1.0 / 60.0;
+const MAX_FIXED_STEPS: u32 = 8;
+const CLOUD_COUNT: usize = 30;
+const MEDAL_SCORE: u128 = 1000;
+const BOMB_DAMAGE: u8 = 10;
+const BOMB_INVULNERABLE_TIME: f64 = 15.0;
+// Score for every bomb left unused when a stage ends
+const BOMB_BONUS: u128 = 2000;
+
+// Everything needed to start a run. Same config and same inputs always play out the same way.
+#[derive(Clone, Copy, Debug)]
+pub struct RunConfig {
+    pub seed: u64,
+    pub mode: Mode,
+    pub fixed_step: bool,
+}
+
+impl RunConfig {
+    pub fn new(seed: u64) -> RunConfig {
+        RunConfig {
+            seed,
+            mode: Mode::Normal,
+            fixed_step: true,
+        }
+    }
+
+    // Fresh seed, mode and stepping as chosen in settings
+    pub fn random() -> RunConfig {
+        let settings = settings::get();
+        RunConfig {
+            mode: settings.mode,
+            fixed_step: settings.fixed_step,
+            ..RunConfig::new(Rand::new().next_u64())
+        }
+    }
+}
+
+// Whole gameplay state. Steps without a renderer so it can be driven by scenes, tests or batch runs alike.
+#[derive(Clone)]
+pub struct World {
+    config: RunConfig,
+    step_accumulator: f64,
+    // Simulated run time in seconds
+    elapsed: f64,
+    score: u128,
+    game_speed: f64,
+    player: Player,
+    stage_runner: StageRunner,
+    score_timer: f64,
+    enemies: Vec<Enemy>,
+    squadrons: Vec<Squadron>,
+    next_squadron_id: u32,
+    // Last bonus, what it was for and how long ago it was scored, for the HUD
+    bonus: Option<(&'static str, u128, f64)>,
+    boss: Option<Boss>,
+    // Player was touching the boss last update, so it only costs a life when contact starts
+    touching_boss: bool,
+    projectiles: Vec<Projectile>,
+    player_projectiles: Vec<Projectile>,
+    explosions : Vec<Explosion>,
+    blasts: Vec<Blast>,
+    pickups: Vec<Pickup>,
+    clouds: Vec<Cloud>,
+    spawn_rng: Rng,
+    pattern_rng: Rng,
+    drop_rng: Rng,
+    // Controls of the previous update, to catch the bomb key going down
+    previous_controls: Controls,
+    // Left over screen shake and red flash from the last hit
+    shake_time: f64,
+    flash_time: f64,
+    // Everything collidable, refilled every update
+    collisions: Collisions,
+}
+
+impl World {
+    pub fn new(config: RunConfig) -> World {
+        World::with_clouds(config, CLOUD_COUNT)
+    }
+
+    fn with_clouds(config: RunConfig, cloud_count: usize) -> World {
+        let mut scenery_rng = stream_rng(config.seed, Stream::Scenery);
+
+        let mut clouds = vec![];
+        for _ in 0..cloud_count {
+            clouds.push(Cloud::new(&mut scenery_rng));
+        }
+
+        World {
+            config,
+            step_accumulator: 0.0,
+            elapsed: 0.0,
+            score: 0,
+            game_speed: config.mode.game_speed(),
+            player: Player::new(config.mode.lives()),
+            stage_runner: StageRunner::new(stage::builtin().expect("stage data is checked before any scene starts")),
+            score_timer: 0.0,
+            enemies: vec![],
+            squadrons: vec![],
+            next_squadron_id: 0,
+            bonus: None,
+            boss: None,
+            touching_boss: false,
+            projectiles: vec![],
+            player_projectiles: vec![],
+            explosions: vec![],
+            blasts: vec![],
+            pickups: vec![],
+            clouds,
+            spawn_rng: stream_rng(config.seed, Stream::Spawning),
+            pattern_rng: stream_rng(config.seed, Stream::Patterns),
+            drop_rng: stream_rng(config.seed, Stream::Drops),
+            previous_controls: Controls::default(),
+            shake_time: 0.0,
+            flash_time: 0.0,
+            collisions: Collisions::new(),
+        }
+    }
+
+    // Feeds frame time into the simulation, in fixed steps when the run asks for them
+    pub fn advance(&mut self, controls: &Controls, delta_time: f64) {
+        if !self.config.fixed_step {
+            self.update(controls, delta_time);
+            return;
+        }
+
+        self.step_accumulator += delta_time;
+
+        let mut steps = 0;
+        while self.step_accumulator >= FIXED_DELTA_TIME {
+            if steps == MAX_FIXED_STEPS {
+                // Way behind, drop the backlog instead of spiralling
+                self.step_accumulator = 0.0;
+                break;
+            }
+            self.update(controls, FIXED_DELTA_TIME);
+            self.step_accumulator -= FIXED_DELTA_TIME;
+            steps += 1;
+        }
+    }
+
+    pub fn update(&mut self, controls: &Controls, delta_time: f64) {
+        if self.is_over() {
+            return;
+        }
+
+        self.elapsed += delta_time;
+
+        self.score_timer += delta_time * self.game_speed;
+        if self.score_timer >= 1.0 {
+            self.score += 1;
+            self.score_timer -= 1.0;
+        }
+
+        let stages_cleared = self.stage_runner.stages_cleared();
+        // Stage times are authored at Normal speed, faster modes run through them quicker
+        let stage_step = delta_time * self.game_speed / Mode::Normal.game_speed();
+        for wave in self.stage_runner.update(stage_step, self.enemies.len(), self.boss.is_some()) {
+            let position_x = match wave.x {
+                Some(x) => x,
+                None => self.spawn_rng.next_i64_in_range(-WALK_AREA_MAX_X as i64, WALK_AREA_MAX_X as i64) as f64,
+            };
+            let behaviour = wave.path.unwrap_or_else(|| Behaviour::toward_center(position_x));
+            let aim = wave.aim.with_skill(self.config.mode.aim_skill());
+
+            match wave.formation {
+                None => self.enemies.push(Enemy::new(wave.enemy, position_x, behaviour, wave.fire, aim)),
+                Some(formation) => {
+                    let id = self.next_squadron_id;
+                    self.next_squadron_id += 1;
+                    self.squadrons.push(Squadron::new(id, formation.size));
+
+                    // Leader first, so it has moved by the time its wingmen line up on it
+                    for (i, offset) in formation.offsets().into_iter().enumerate() {
+                        let enemy = Enemy::new(wave.enemy, position_x, behaviour.clone(), wave.fire.clone(), aim);
+                        self.enemies.push(match i {
+                            0 => enemy.lead(id),
+                            _ => enemy.fly_wing(id, offset),
+                        });
+                    }
+                }
+            }
+        }
+
+        if self.stage_runner.stages_cleared() > stages_cleared {
+            let bonus = self.player.restock_bombs() as u128 * BOMB_BONUS;
+            if bonus > 0 {
+                self.score += bonus;
+                self.bonus = Some(("BOMB BONUS", bonus, 0.0));
+            }
+        }
+
+        if let Some(kind) = self.stage_runner.take_boss() {
+            self.boss = Some(Boss::new(kind));
+        }
+
+        if let Some((_, _, time)) = &mut self.bonus {
+            *time += delta_time;
+        }
+
+        self.shake_time -= self.game_speed * delta_time;
+        self.flash_time -= self.game_speed * delta_time;
+        let mut player_hit = false;
+
+        for cloud in &mut self.clouds {
+            cloud.on_update(controls, delta_time, self.game_speed);
+        }
+
+        // Bomb goes off on the key press, Enter during play
+        let pressed = controls.pressed(&self.previous_controls);
+        self.previous_controls = *controls;
+        if pressed.enter && self.player.use_bomb() {
+            self.detonate_bomb();
+        }
+
+        let mut enemies_to_remove = vec![false; self.enemies.len()];
+        for i in 0..self.enemies.len() {
+            // Taken out by the bomb
+            if !self.enemies[i].alive() {
+                enemies_to_remove[i] = true;
+                continue;
+            }
+
+            if let Some(squadron) = self.enemies[i].formation_squadron() {
+                let leader = self.enemies[..i].iter().find(|other| other.leads(squadron)).map(|leader| leader.position());
+                let leader_destroyed = self.squadrons.iter().any(|other| other.id == squadron && other.leader_destroyed());
+                match leader {
+                    Some(leader) => self.enemies[i].hold_formation(leader),
+                    // A leader that got away hands its path over when it's removed
+                    None if leader_destroyed => self.enemies[i].break_formation(),
+                    None => {}
+                }
+            }
+
+            let enemy = &mut self.enemies[i];
+            enemy.set_target(self.player.position());
+            enemy.on_update(controls, delta_time, self.game_speed);
+
+            let target = Target { position: self.player.position(), velocity: self.player.velocity() };
+            for bullet in enemy.fire(self.game_speed * delta_time, target, &mut self.pattern_rng) {
+                let projectile = Projectile::new(bullet.velocity, bullet.position, FlaskColor::Red as u8)
+                    .on_layer(Layer::EnemyBullet);
+                self.projectiles.push(match bullet.homing {
+                    true => projectile.homing(Homing::enemy()),
+                    false => projectile,
+                });
+            }
+
+            if enemy.off_screen() {
+                enemies_to_remove[i] = true;
+            }
+        }
+
+        if let Some(boss) = &mut self.boss {
+            for event in boss.update(delta_time, self.game_speed, self.player.position()) {
+                match event {
+                    BossEvent::Shot { position, velocity } => {
+                        let projectile = Projectile::new(velocity, position, FlaskColor::Red as u8);
+                        self.projectiles.push(projectile.on_layer(Layer::EnemyBullet));
+                    }
+                    BossEvent::Explosion { position } => self.explosions.push(Explosion::new(position)),
+                }
+            }
+        }
+
+        let player_position = [self.player.position()];
+        let mut projectiles_to_remove = vec![false; self.projectiles.len()];
+        for i in 0..self.projectiles.len() {
+            let projectile = &mut self.projectiles[i];
+            if projectile.is_homing() {
+                projectile.seek(&player_position);
+            }
+            projectile.on_update(controls, delta_time, self.game_speed);
+
+            if projectile.position().y < -20.0 || projectile.distance(&self.player) > 300.0 || projectile.expired() {
+                projectiles_to_remove[i] = true;
+            }
+
+            if self.blasts.iter().any(|blast| blast.covers(&*projectile)) {
+                projectiles_to_remove[i] = true;
+            }
+        }
+
+        self.player.on_update(controls, delta_time, self.game_speed);
+
+        self.player_projectiles.extend(self.player.fire());
+
+        let targets = missile_targets(&self.enemies, self.boss.as_ref());
+        let mut player_projectiles_to_remove = vec![false; self.player_projectiles.len()];
+        for i in 0..self.player_projectiles.len() {
+            let projectile = &mut self.player_projectiles[i];
+            if projectile.is_homing() {
+                projectile.seek(&targets);
+            }
+            projectile.on_update(controls, delta_time, self.game_speed);
+
+            if projectile.distance(&self.player) > 400.0 || projectile.expired() {
+                player_projectiles_to_remove[i] = true;
+            }
+        }
+
+        let mut pickups_to_remove = vec![false; self.pickups.len()];
+        for i in 0..self.pickups.len() {
+            let pickup = &mut self.pickups[i];
+            pickup.set_target(self.player.position());
+            pickup.on_update(controls, delta_time, self.game_speed);
+
+            if pickup.expired() {
+                pickups_to_remove[i] = true;
+            }
+        }
+
+        // Everything has moved, one collision pass over all of it
+        self.collisions.clear();
+        self.collisions.add(Handle::Player, &self.player);
+        for (i, projectile) in self.player_projectiles.iter().enumerate() {
+            // Player shots are added first so a shot meets enemies before the boss
+            if !player_projectiles_to_remove[i] {
+                self.collisions.add(Handle::PlayerBullet(i), projectile);
+            }
+        }
+        for (i, enemy) in self.enemies.iter().enumerate() {
+            if enemy.alive() && !enemies_to_remove[i] {
+                self.collisions.add(Handle::Enemy(i), enemy);
+            }
+        }
+        if let Some(boss) = &self.boss {
+            // Parts of a boss still flying in can be rammed but not shot
+            let layer = match boss.vulnerable() {
+                true => Layer::Enemy,
+                false => Layer::Hazard,
+            };
+            for (part, position, collider) in boss.solid_parts() {
+                self.collisions.add_collider(Handle::BossPart(part), layer, Mask::of(&[Layer::Player]), position, collider);
+            }
+        }
+        for (i, projectile) in self.projectiles.iter().enumerate() {
+            if !projectiles_to_remove[i] {
+                self.collisions.add(Handle::EnemyBullet(i), projectile);
+            }
+        }
+        for (i, pickup) in self.pickups.iter().enumerate() {
+            if !pickups_to_remove[i] {
+                self.collisions.add(Handle::Pickup(i), pickup);
+            }
+        }
+
+        let mut touching_boss = false;
+        for hit in self.collisions.hits() {
+            match (hit.source, hit.target) {
+                (Handle::EnemyBullet(i), Handle::Player) => {
+                    player_hit |= self.player.damage();
+                    projectiles_to_remove[i] = true;
+                }
+                // Ramming hurts both
+                (Handle::Enemy(i), Handle::Player) => {
+                    let enemy = &mut self.enemies[i];
+                    if !enemy.alive() {
+                        continue;
+                    }
+                    player_hit |= self.player.damage();
+                    enemy.destroy();
+                    enemies_to_remove[i] = true;
+                    self.explosions.push(Explosion::new(enemy.position()));
+                }
+                // Running into the boss only hurts the player, and only when contact starts
+                (Handle::BossPart(_), Handle::Player) => touching_boss = true,
+                (Handle::PlayerBullet(i), Handle::Enemy(y)) => {
+                    // Already spent on something else
+                    if player_projectiles_to_remove[i] {
+                        continue;
+                    }
+                    let projectile = &self.player_projectiles[i];
+                    let enemy = &mut self.enemies[y];
+                    if !enemy.alive() {
+                        continue;
+                    }
+                    enemy.damage(projectile.damage());
+                    if !projectile.is_piercing() {
+                        player_projectiles_to_remove[i] = true;
+                    }
+                    if !enemy.alive() {
+                        self.score += 100;
+                        enemies_to_remove[y] = true;
+                        self.explosions.push(Explosion::new(enemy.position()));
+                        if let Some(kind) = enemy.kind().drops().roll(&mut self.drop_rng) {
+                            self.pickups.push(Pickup::new(kind, enemy.position()));
+                        }
+                    }
+                }
+                (Handle::PlayerBullet(i), Handle::BossPart(part)) => {
+                    if player_projectiles_to_remove[i] {
+                        continue;
+                    }
+                    let projectile = &self.player_projectiles[i];
+                    if !projectile.is_piercing() {
+                        player_projectiles_to_remove[i] = true;
+                    }
+                    if let Some(boss) = &mut self.boss {
+                        if let Some(score) = boss.damage(part, projectile.damage()) {
+                            self.score += score;
+                            self.explosions.push(Explosion::new(boss.part_position(part)));
+                        }
+                    }
+                }
+                (Handle::Pickup(i), Handle::Player) => {
+                    match self.pickups[i].kind() {
+                        PickupKind::Weapon(kind) => self.player.collect_weapon(kind),
+                        PickupKind::ExtraLife => self.player.extra_life(),
+                        PickupKind::Shield => self.player.shield(),
+                        PickupKind::Bomb => self.player.add_bomb(),
+                        PickupKind::Medal => self.score += MEDAL_SCORE,
+                    }
+                    pickups_to_remove[i] = true;
+                }
+                _ => {}
+            }
+        }
+
+        if touching_boss && !self.touching_boss {
+            player_hit |= self.player.damage();
+        }
+        self.touching_boss = touching_boss;
+        if self.boss.as_ref().map_or(false, |boss| boss.is_done()) {
+            self.boss = None;
+        }
+
+        if player_hit {
+            self.shake_time = HIT_FEEDBACK.shake_time;
+            self.flash_time = HIT_FEEDBACK.flash_time;
+            for (i, projectile) in self.projectiles.iter().enumerate() {
+                if projectile.distance(&self.player) <= HIT_FEEDBACK.clear_radius {
+                    projectiles_to_remove[i] = true;
+                }
+            }
+        }
+
+        if self.is_over() {
+            return;
+        }
+
+        for blast in &mut self.blasts {
+            blast.on_update(controls, delta_time, self.game_speed);
+        }
+        self.blasts.retain(|blast| blast.alive());
+
+        let mut explosions_to_remove = vec![];
+        for i in 0..self.explosions.len() {
+            let explosion = &mut self.explosions[i];
+            explosion.on_update(controls, delta_time, self.game_speed);
+
+            if !explosion.alive() {
+                explosions_to_remove.push(i);
+            }
+        }
+
+        // Remove things
+        let mut removed_enemies = vec![];
+        for (enemy, removed) in std::mem::take(&mut self.enemies).into_iter().zip(enemies_to_remove) {
+            match removed {
+                true => removed_enemies.push(enemy),
+                false => self.enemies.push(enemy),
+            }
+        }
+        for enemy in removed_enemies {
+            if let Some(id) = enemy.squadron() {
+                if enemy.is_leader() && enemy.alive() {
+                    for wingman in &mut self.enemies {
+                        if wingman.formation_squadron() == Some(id) {
+                            wingman.follow_path_of(&enemy);
+                        }
+                    }
+                }
+                self.remove_squadron_member(id, !enemy.alive(), enemy.is_leader());
+            }
+        }
+        remove_flagged(&mut self.projectiles, &projectiles_to_remove);
+        remove_flagged(&mut self.player_projectiles, &player_projectiles_to_remove);
+        remove_flagged(&mut self.pickups, &pickups_to_remove);
+        explosions_to_remove.sort();
+        for i in explosions_to_remove.iter().rev() {
+            self.explosions.remove(*i);
+        }
+    }
+
+    // Clears enemy fire, hits everything in range and keeps the player safe for a moment
+    fn detonate_bomb(&mut self) {
+        let blast = Blast::new(self.player.position());
+        self.projectiles.clear();
+        self.player.make_invulnerable(BOMB_INVULNERABLE_TIME);
+
+        for enemy in &mut self.enemies {
+            if !enemy.alive() || enemy.distance(&blast) > BLAST_RADIUS {
+                continue;
+            }
+            enemy.damage(BOMB_DAMAGE);
+            if !enemy.alive() {
+                self.score += 100;
+                self.explosions.push(Explosion::new(enemy.position()));
+                if let Some(kind) = enemy.kind().drops().roll(&mut self.drop_rng) {
+                    self.pickups.push(Pickup::new(kind, enemy.position()));
+                }
+            }
+        }
+
+        if let Some(boss) = &mut self.boss {
+            for part in boss.parts_within(blast.position(), BLAST_RADIUS) {
+                if let Some(score) = boss.damage(part, BOMB_DAMAGE) {
+                    self.score += score;
+                    self.explosions.push(Explosion::new(boss.part_position(part)));
+                }
+            }
+        }
+
+        self.blasts.push(blast);
+    }
+
+    fn remove_squadron_member(&mut self, id: u32, destroyed: bool, leader: bool) {
+        let index = match self.squadrons.iter().position(|squadron| squadron.id == id) {
+            Some(index) => index,
+            None => return,
+        };
+
+        if let Some(bonus) = self.squadrons[index].remove_member(destroyed, leader) {
+            self.score += bonus;
+            self.bonus = Some(("SQUADRON BONUS", bonus, 0.0));
+        }
+        if self.squadrons[index].is_done() {
+            self.squadrons.remove(index);
+        }
+    }
+
+    pub fn config(&self) -> RunConfig {
+        self.config
+    }
+
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn is_over(&self) -> bool {
+        !self.player.alive()
+    }
+
+    pub fn score(&self) -> u128 {
+        self.score
+    }
+
+    // How far the screen may be thrown this frame, zero when not shaking
+    pub fn shake(&self) -> i64 {
+        match self.shake_time > 0.0 {
+            true => HIT_FEEDBACK.shake_strength,
+            false => 0,
+        }
+    }
+
+    pub fn flashing(&self) -> bool {
+        self.flash_time > 0.0
+    }
+
+    // Most recent bonus, what it was for and seconds since it was scored
+    pub fn bonus(&self) -> Option<(&'static str, u128, f64)> {
+        self.bonus
+    }
+
+    pub fn stage_runner(&self) -> &StageRunner {
+        &self.stage_runner
+    }
+
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
+    }
+
+    pub fn boss(&self) -> Option<&Boss> {
+        self.boss.as_ref()
+    }
+
+    pub fn projectiles(&self) -> &[Projectile] {
+        &self.projectiles
+    }
+
+    pub fn player_projectiles(&self) -> &[Projectile] {
+        &self.player_projectiles
+    }
+
+    pub fn explosions(&self) -> &[Explosion] {
+        &self.explosions
+    }
+
+    pub fn blasts(&self) -> &[Blast] {
+        &self.blasts
+    }
+
+    pub fn pickups(&self) -> &[Pickup] {
+        &self.pickups
+    }
+
+    pub fn clouds(&self) -> &[Cloud] {
+        &self.clouds
+    }
+}
+
+// Drops items flagged for removal, keeping the rest in order. Anything added after flagging stays.
+fn remove_flagged<T>(items: &mut Vec<T>, flagged: &[bool]) {
+    let mut index = 0;
+    items.retain(|_| {
+        let keep = !flagged.get(index).copied().unwrap_or(false);
+        index += 1;
+        keep
+    });
+}
+
+// Everything the player's missiles can chase
+fn missile_targets(enemies: &[Enemy], boss: Option<&Boss>) -> Vec<Vec2> {
+    let mut targets: Vec<Vec2> = enemies.iter()
+        .filter(|enemy| enemy.alive())
+        .map(|enemy| enemy.position())
+        .collect();
+    if let Some(boss) = boss {
+        targets.extend(boss.targets());
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aim::Aim;
+    use crate::enemy::EnemyKind;
+
+    fn enemy_positions(world: &World) -> Vec<Vec2> {
+        world.enemies().iter().map(|enemy| enemy.position()).collect()
+    }
+
+    fn enemy_bullet_at(position: Vec2) -> Projectile {
+        Projectile::new(Vec2::ZERO, position, FlaskColor::Red as u8).on_layer(Layer::EnemyBullet)
+    }
+
+    // Replays rely on this: same seed and same controls, same run
+    #[test]
+    fn same_seed_and_controls_play_out_the_same() {
+        let config = RunConfig::new(99);
+        let mut first = World::new(config);
+        let mut second = World::new(config);
+
+        for i in 0..1500 {
+            let controls = Controls {
+                left: (i / 70) % 3 == 0,
+                right: (i / 70) % 3 == 2,
+                up: i % 150 < 30,
+                enter: i % 500 == 250,
+                ..Controls::default()
+            };
+            first.update(&controls, FIXED_DELTA_TIME);
+            second.update(&controls, FIXED_DELTA_TIME);
+        }
+
+        // Enemies spawned at random spots and fired, so the streams were drawn from
+        assert_ne!(first.spawn_rng.clone().next_u64(), stream_rng(config.seed, Stream::Spawning).next_u64());
+        assert_ne!(first.pattern_rng.clone().next_u64(), stream_rng(config.seed, Stream::Patterns).next_u64());
+        assert_eq!(first.score(), second.score());
+        assert_eq!(first.player().position(), second.player().position());
+        assert_eq!(first.player().health(), second.player().health());
+        assert_eq!(enemy_positions(&first), enemy_positions(&second));
+        let projectile_positions = |world: &World| world.projectiles().iter().map(|projectile| projectile.position()).collect::<Vec<Vec2>>();
+        assert_eq!(projectile_positions(&first), projectile_positions(&second));
+        assert_eq!(first.spawn_rng.next_u64(), second.spawn_rng.next_u64());
+        assert_eq!(first.pattern_rng.next_u64(), second.pattern_rng.next_u64());
+        assert_eq!(first.drop_rng.next_u64(), second.drop_rng.next_u64());
+    }
+
+    // Scenery has its own stream, so more or fewer clouds must not move a single spawn
+    #[test]
+    fn cloud_count_does_not_change_spawning() {
+        let config = RunConfig::new(42);
+        let mut few = World::with_clouds(config, 1);
+        let mut many = World::with_clouds(config, CLOUD_COUNT * 2);
+
+        let controls = Controls::default();
+        for _ in 0..600 {
+            few.update(&controls, FIXED_DELTA_TIME);
+            many.update(&controls, FIXED_DELTA_TIME);
+        }
+
+        assert!(!few.enemies().is_empty());
+        assert_eq!(enemy_positions(&few), enemy_positions(&many));
+        assert_eq!(few.spawn_rng.next_u64(), many.spawn_rng.next_u64());
+    }
+
+    // Overlapping bullets landing in the same update only cost one life
+    #[test]
+    fn two_hits_in_one_update_cost_one_life() {
+        let mut world = World::new(RunConfig::new(1));
+        let lives = world.player().health();
+        let position = world.player().position();
+        world.projectiles.push(enemy_bullet_at(position));
+        world.projectiles.push(enemy_bullet_at(position + Vec2::new(1.0, 0.0)));
+
+        world.update(&Controls::default(), FIXED_DELTA_TIME);
+
+        assert_eq!(world.player().health(), lives - 1);
+        assert!(world.projectiles().is_empty());
+        assert!(world.shake() > 0 && world.flashing());
+    }
+
+    #[test]
+    fn kamikaze_ram_costs_a_life() {
+        let mut world = World::new(RunConfig::new(1));
+        let lives = world.player().health();
+        let kamikaze = Behaviour::from_name("kamikaze").unwrap();
+        world.enemies.push(Enemy::new(EnemyKind::Fighter, 0.0, kamikaze, None, Aim::PLAIN).at(world.player().position()));
+
+        world.update(&Controls::default(), FIXED_DELTA_TIME);
+
+        assert_eq!(world.player().health(), lives - 1);
+        assert!(world.enemies().is_empty());
+    }
+
+    // Only kamikazes ram, other enemies fly over the player
+    #[test]
+    fn other_enemies_pass_over_the_player() {
+        let mut world = World::new(RunConfig::new(1));
+        let lives = world.player().health();
+        world.enemies.push(Enemy::new(EnemyKind::Fighter, 0.0, Behaviour::FlyL2R, None, Aim::PLAIN).at(world.player().position()));
+
+        world.update(&Controls::default(), FIXED_DELTA_TIME);
+
+        assert_eq!(world.player().health(), lives);
+        assert_eq!(world.enemies().len(), 1);
+    }
+
+    // Scout and a shot placed so that `delta_time` worth of flying takes the shot 30 pixels straight through
+    // where the scout ends up, three times the scout's collider width
+    fn shot_through_scout(config: RunConfig, delta_time: f64) -> (World, Vec2) {
+        let mut world = World::new(config);
+        let enemy = Enemy::new(EnemyKind::Scout, 0.0, Behaviour::FlyL2R, None, Aim::PLAIN);
+        let mut predicted = enemy.clone();
+        predicted.on_update(&Controls::default(), delta_time, world.game_speed);
+        world.enemies.push(enemy);
+
+        let start = predicted.position() - Vec2::new(0.0, 15.0);
+        let speed = 30.0 / (delta_time * world.game_speed);
+        world.player_projectiles.push(Projectile::new(Vec2::new(0.0, speed), start, FlaskColor::Yellow as u8).with_damage(2));
+        (world, start)
+    }
+
+    fn assert_scout_shot_down(world: &World, start: Vec2) {
+        assert!(world.enemies().is_empty());
+        assert!(world.score() >= 100);
+        assert!(world.player_projectiles().iter().all(|projectile| projectile.position().y < start.y));
+    }
+
+    // A shot covering more than an enemy's width in one slow frame still hits it on the way past
+    #[test]
+    fn fast_shot_hits_enemy_it_skips_over() {
+        let (mut world, start) = shot_through_scout(RunConfig::new(1), 0.1);
+        world.update(&Controls::default(), 0.1);
+        assert_scout_shot_down(&world, start);
+    }
+
+    // Same through advance, the way the game steps: a 10 fps frame in one go with fixed stepping off,
+    // and split into fixed steps with it on
+    #[test]
+    fn low_frame_rate_shot_hits_enemy_it_skips_over() {
+        for fixed_step in [false, true] {
+            let config = RunConfig { fixed_step, ..RunConfig::new(1) };
+            let (mut world, start) = shot_through_scout(config, 0.1);
+            world.advance(&Controls::default(), 0.1);
+            assert_scout_shot_down(&world, start);
+        }
+    }
+}